@@ -10,6 +10,7 @@ mod board_widget {
     use ajedrez::{ChessBoard as AjedrezChessBoard};
 
     pub struct Board<'a> {
+        #[allow(dead_code)]
         board: &'a AjedrezChessBoard,
         width: f32,
         height: f32,
@@ -65,7 +66,7 @@ mod board_widget {
     }
 }
 
-use iced::{Element, Error, Length, Sandbox, Settings};
+use iced::{Element, Length, Sandbox, Settings};
 use iced::Alignment;
 use iced::widget::{column, container, text};
 use ajedrez::ChessBoard as AjedrezChessBoard;
@@ -100,10 +101,10 @@ impl Sandbox for Chess {
         println!("update")
     }
 
-    fn view(&self) -> Element<Message> {
+    fn view(&self) -> Element<'_, Message> {
         let content = column![
             board_widget::Board::new(&self.board),
-            text(format!("The chess status here ...")),
+            text("The chess status here ..."),
         ]
             .padding(20)
             .spacing(20)
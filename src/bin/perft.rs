@@ -0,0 +1,51 @@
+//! Counts the leaf nodes of the move tree of a position, split by the first move.
+//!
+//! Usage: `perft <depth> [FEN]`. Without a FEN the initial position is used.
+
+use std::env;
+use std::process::exit;
+use std::time::Instant;
+
+use ajedrez::{FENStringParsing, INITIAL_FEN_BOARD};
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let depth = match args.first().map(|d| d.parse::<u32>()) {
+        Some(Ok(depth)) => depth,
+        _ => {
+            eprintln!("Usage: perft <depth> [FEN]");
+            exit(2);
+        }
+    };
+    let fen = if args.len() > 1 {
+        args[1..].join(" ")
+    } else {
+        INITIAL_FEN_BOARD.to_string()
+    };
+    let board = match fen.parse_fen() {
+        Ok(board) => board,
+        Err(e) => {
+            eprintln!("{}: {}", e, fen);
+            exit(1);
+        }
+    };
+
+    let start = Instant::now();
+    let mut divide: Vec<(String, u64)> = board
+        .perft_divide(depth)
+        .iter()
+        .map(|(mov, nodes)| (board.to_uci(*mov), *nodes))
+        .collect();
+    divide.sort();
+    for (mov, nodes) in &divide {
+        println!("{}: {}", mov, nodes);
+    }
+    let nodes: u64 = if depth == 0 {
+        1
+    } else {
+        divide.iter().map(|(_, nodes)| nodes).sum()
+    };
+    println!();
+    println!("Nodes searched: {}", nodes);
+    println!("Time: {:.3}s", start.elapsed().as_secs_f64());
+}
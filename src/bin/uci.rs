@@ -0,0 +1,266 @@
+//! A chess engine speaking the Universal Chess Interface, to play with ajedrez from GUIs like
+//! Cute Chess or Arena.
+//!
+//! Usage: `uci`, then UCI commands on the standard input. The search runs on its own thread, so
+//! `stop`, `isready` and `quit` are answered while it thinks.
+
+use std::io::{self, BufRead};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use ajedrez::{
+    search, ChessBoard, FENStringParsing, Move, Score, SearchInfo, SearchLimits, INITIAL_FEN_BOARD,
+};
+
+/// The moves left until the next time control, when the GUI doesn't tell
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+/// Time kept aside for the communication with the GUI
+const MOVE_OVERHEAD: Duration = Duration::from_millis(50);
+
+/// A search running on its own thread
+struct SearchThread {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+impl SearchThread {
+    /// Stops the search and waits for it to report its best move
+    fn stop(self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.handle.join().expect("The search thread panicked");
+    }
+}
+
+struct Engine {
+    /// The position to search, `None` after a `position` command that couldn't be read
+    board: Option<ChessBoard>,
+    chess960: bool,
+    search: Option<SearchThread>,
+}
+
+impl Engine {
+    fn new() -> Self {
+        Engine {
+            board: Some(start_position(false)),
+            chess960: false,
+            search: None,
+        }
+    }
+
+    /// Handles one command. Returns `false` on `quit`.
+    fn command(&mut self, line: &str) -> bool {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let Some((&command, args)) = tokens.split_first() else {
+            return true;
+        };
+        match command {
+            "uci" => {
+                println!("id name ajedrez {}", env!("CARGO_PKG_VERSION"));
+                println!("id author {}", env!("CARGO_PKG_AUTHORS"));
+                println!("option name UCI_Chess960 type check default false");
+                println!("uciok");
+            }
+            "isready" => println!("readyok"),
+            "ucinewgame" => {
+                self.stop_search();
+                self.board = Some(start_position(self.chess960));
+            }
+            "setoption" => self.set_option(args),
+            "position" => {
+                self.stop_search();
+                // A search on the previous position would answer a move of another game
+                self.board = match self.set_position(args) {
+                    Ok(board) => Some(board),
+                    Err(message) => {
+                        println!("info string {message}");
+                        None
+                    }
+                };
+            }
+            "go" => {
+                self.stop_search();
+                self.go(args);
+            }
+            "stop" => self.stop_search(),
+            "quit" => {
+                self.stop_search();
+                return false;
+            }
+            _ => println!("info string Unknown command: {command}"),
+        }
+        true
+    }
+
+    fn stop_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            search.stop();
+        }
+    }
+
+    /// `setoption name <name> [value <value>]`
+    fn set_option(&mut self, args: &[&str]) {
+        let text = args.join(" ");
+        let text = text.strip_prefix("name ").unwrap_or(&text);
+        let (name, value) = match text.split_once(" value ") {
+            Some((name, value)) => (name.trim(), value.trim()),
+            None => (text.trim(), ""),
+        };
+        match name {
+            "UCI_Chess960" => {
+                self.chess960 = value == "true";
+                if let Some(board) = &mut self.board {
+                    board.chess960 = self.chess960;
+                }
+            }
+            _ => println!("info string Unknown option: {name}"),
+        }
+    }
+
+    /// `position [startpos | fen <fen>] [moves <move>...]`
+    fn set_position(&self, args: &[&str]) -> Result<ChessBoard, String> {
+        let moves_at = args.iter().position(|&arg| arg == "moves");
+        let (position, moves) = match moves_at {
+            Some(index) => (&args[..index], &args[index + 1..]),
+            None => (args, &[][..]),
+        };
+        let mut board = match position {
+            ["startpos"] => start_position(self.chess960),
+            ["fen", fen @ ..] => {
+                let fen = fen.join(" ");
+                let board = if self.chess960 {
+                    fen.parse_fen_strict_960()
+                } else {
+                    fen.parse_fen_strict()
+                };
+                board.map_err(|e| format!("Invalid FEN: {e}"))?
+            }
+            _ => return Err(format!("Invalid position: {}", position.join(" "))),
+        };
+        for uci in moves {
+            let mov = board
+                .parse_uci(uci)
+                .map_err(|e| format!("Invalid move {uci}: {e}"))?;
+            if !board.legal_moves().contains(&mov) {
+                return Err(format!("Illegal move {uci}"));
+            }
+            board
+                .make_move(mov)
+                .map_err(|e| format!("Invalid move {uci}: {e}"))?;
+        }
+        Ok(board)
+    }
+
+    /// `go [depth <plies>] [movetime <ms>] [wtime <ms>] [btime <ms>] [winc <ms>] [binc <ms>]
+    /// [movestogo <moves>] [infinite]`
+    fn go(&mut self, args: &[&str]) {
+        let Some(board) = self.board.clone() else {
+            println!("info string No position to search");
+            println!("bestmove {}", Move::NULL);
+            return;
+        };
+        let mut limits = SearchLimits::default();
+        let mut infinite = false;
+        let (mut clock, mut increment, mut moves_to_go) = (None, 0, DEFAULT_MOVES_TO_GO);
+        let white = board.active_color == ajedrez::Color::White;
+        let mut args = args.iter();
+        while let Some(&arg) = args.next() {
+            let mut number = || args.next().and_then(|value| value.parse::<u64>().ok());
+            match arg {
+                "depth" => limits.depth = number().map(|depth| depth as u32),
+                "movetime" => limits.time = number().map(Duration::from_millis),
+                "wtime" if white => clock = number(),
+                "btime" if !white => clock = number(),
+                "winc" if white => increment = number().unwrap_or(0),
+                "binc" if !white => increment = number().unwrap_or(0),
+                "movestogo" => {
+                    moves_to_go = number().map_or(DEFAULT_MOVES_TO_GO, |moves| moves.max(1) as u32)
+                }
+                "infinite" => infinite = true,
+                // The clock of the opponent
+                "wtime" | "btime" | "winc" | "binc" => {
+                    number();
+                }
+                _ => {}
+            }
+        }
+        if infinite {
+            limits = SearchLimits {
+                depth: limits.depth,
+                time: None,
+            };
+        } else if let (None, Some(clock)) = (limits.time, clock) {
+            let share = clock / moves_to_go as u64 + increment / 2;
+            let time = Duration::from_millis(share.min(clock)).saturating_sub(MOVE_OVERHEAD);
+            limits.time = Some(time.max(Duration::from_millis(1)));
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let stop = Arc::clone(&stop);
+            thread::spawn(move || {
+                let best = search(&board, &limits, &stop, |info| print_info(&board, info));
+                // An infinite search waits for `stop` before telling its move
+                while infinite && !stop.load(Ordering::Relaxed) {
+                    thread::sleep(Duration::from_millis(10));
+                }
+                let best = best.map_or_else(|| Move::NULL.to_string(), |mov| board.to_uci(mov));
+                println!("bestmove {best}");
+            })
+        };
+        self.search = Some(SearchThread { stop, handle });
+    }
+}
+
+fn start_position(chess960: bool) -> ChessBoard {
+    let mut board = INITIAL_FEN_BOARD
+        .parse_fen()
+        .expect("The initial position is valid");
+    board.chess960 = chess960;
+    board
+}
+
+fn print_info(board: &ChessBoard, info: &SearchInfo) {
+    let score = match info.score {
+        Score::Centipawns(cp) => format!("cp {cp}"),
+        Score::Mate(moves) => format!("mate {moves}"),
+    };
+    let millis = info.time.as_millis().max(1);
+    // The moves are written on the positions they are played on, for Chess960 castling
+    let mut board = board.clone();
+    let pv: Vec<String> = info
+        .pv
+        .iter()
+        .map(|&mov| {
+            let uci = board.to_uci(mov);
+            board
+                .make_move(mov)
+                .expect("The principal variation is legal");
+            uci
+        })
+        .collect();
+    println!(
+        "info depth {} score {} nodes {} nps {} time {} pv {}",
+        info.depth,
+        score,
+        info.nodes,
+        info.nodes as u128 * 1000 / millis,
+        millis,
+        pv.join(" ")
+    );
+}
+
+fn main() {
+    let mut engine = Engine::new();
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if !engine.command(&line) {
+            return;
+        }
+    }
+    // The GUI closed the input
+    engine.stop_search();
+}
@@ -0,0 +1,195 @@
+//! Bitboards: sets of squares packed in a `u64`, and the precomputed attack tables built on them.
+//!
+//! Bit `row * 8 + col` stands for the square at the zero-based `(row, col)` position, the same
+//! coordinates used everywhere else in the crate. So bit 0 is a8 and bit 63 is h1.
+//!
+//! ```
+//! use ajedrez::bitboard::{knight_attacks, square_index, squares};
+//! use ajedrez::pos_from_str;
+//!
+//! let b1 = square_index(pos_from_str("b1").unwrap());
+//! let targets: Vec<(usize, usize)> = squares(knight_attacks(b1)).collect();
+//! assert_eq!(3, targets.len());
+//! assert!(targets.contains(&pos_from_str("c3").unwrap()));
+//! ```
+
+use crate::{Color, BOARD_SIZE};
+
+/// A set of squares, one bit per square
+pub type Bitboard = u64;
+
+/// The number of squares of the board, and so the number of bits of a [Bitboard]
+pub const SQUARES: usize = BOARD_SIZE * BOARD_SIZE;
+
+/// Converts a zero-based `(row, col)` position into a bit index
+pub fn square_index(position: (usize, usize)) -> usize {
+    position.0 * BOARD_SIZE + position.1
+}
+
+/// Converts a bit index back into a zero-based `(row, col)` position
+pub fn square_position(index: usize) -> (usize, usize) {
+    (index / BOARD_SIZE, index % BOARD_SIZE)
+}
+
+/// A bitboard with only the given position set
+pub fn square_bit(position: (usize, usize)) -> Bitboard {
+    1 << square_index(position)
+}
+
+/// Iterates over the positions of the squares of a bitboard, from a8 to h1
+pub fn squares(bitboard: Bitboard) -> Squares {
+    Squares(bitboard)
+}
+
+/// Iterator returned by [squares]
+pub struct Squares(Bitboard);
+
+impl Iterator for Squares {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0 == 0 {
+            return None;
+        }
+        let index = self.0.trailing_zeros() as usize;
+        // Clear the lowest bit
+        self.0 &= self.0 - 1;
+        Some(square_position(index))
+    }
+}
+
+/// Builds the attack table of a piece that jumps by fixed offsets (knight, king and pawns)
+const fn leaper_table(offsets: &[(isize, isize)]) -> [Bitboard; SQUARES] {
+    let mut table = [0; SQUARES];
+    let mut index = 0;
+    while index < SQUARES {
+        let row = (index / BOARD_SIZE) as isize;
+        let col = (index % BOARD_SIZE) as isize;
+        let mut i = 0;
+        while i < offsets.len() {
+            let (r, c) = (row + offsets[i].0, col + offsets[i].1);
+            if r >= 0 && r < BOARD_SIZE as isize && c >= 0 && c < BOARD_SIZE as isize {
+                table[index] |= 1 << (r as usize * BOARD_SIZE + c as usize);
+            }
+            i += 1;
+        }
+        index += 1;
+    }
+    table
+}
+
+/// Builds the table of the squares reached from every square by sliding in one direction, up to
+/// the edge of the board
+const fn ray_table(direction: (isize, isize)) -> [Bitboard; SQUARES] {
+    let mut table = [0; SQUARES];
+    let mut index = 0;
+    while index < SQUARES {
+        let mut r = (index / BOARD_SIZE) as isize + direction.0;
+        let mut c = (index % BOARD_SIZE) as isize + direction.1;
+        while r >= 0 && r < BOARD_SIZE as isize && c >= 0 && c < BOARD_SIZE as isize {
+            table[index] |= 1 << (r as usize * BOARD_SIZE + c as usize);
+            r += direction.0;
+            c += direction.1;
+        }
+        index += 1;
+    }
+    table
+}
+
+const KNIGHT_ATTACKS: [Bitboard; SQUARES] = leaper_table(&[
+    (1, 2),
+    (2, 1),
+    (-1, 2),
+    (-2, 1),
+    (1, -2),
+    (2, -1),
+    (-1, -2),
+    (-2, -1),
+]);
+
+const KING_ATTACKS: [Bitboard; SQUARES] = leaper_table(&[
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+]);
+
+// White pawns move towards row 0, black pawns towards row 7
+const WHITE_PAWN_ATTACKS: [Bitboard; SQUARES] = leaper_table(&[(-1, -1), (-1, 1)]);
+const BLACK_PAWN_ATTACKS: [Bitboard; SQUARES] = leaper_table(&[(1, -1), (1, 1)]);
+
+/// The rays of rooks and bishops. The flag tells whether the bit index grows along the ray, which
+/// decides how the nearest blocker is found.
+const ROOK_RAYS: [([Bitboard; SQUARES], bool); 4] = [
+    (ray_table((-1, 0)), false),
+    (ray_table((1, 0)), true),
+    (ray_table((0, -1)), false),
+    (ray_table((0, 1)), true),
+];
+const BISHOP_RAYS: [([Bitboard; SQUARES], bool); 4] = [
+    (ray_table((-1, -1)), false),
+    (ray_table((-1, 1)), false),
+    (ray_table((1, -1)), true),
+    (ray_table((1, 1)), true),
+];
+
+/// Squares attacked by a knight on `square`
+pub fn knight_attacks(square: usize) -> Bitboard {
+    KNIGHT_ATTACKS[square]
+}
+
+/// Squares attacked by a king on `square`
+pub fn king_attacks(square: usize) -> Bitboard {
+    KING_ATTACKS[square]
+}
+
+/// Squares attacked (diagonally) by a pawn of the given color on `square`
+pub fn pawn_attacks(color: Color, square: usize) -> Bitboard {
+    match color {
+        Color::White => WHITE_PAWN_ATTACKS[square],
+        Color::Black => BLACK_PAWN_ATTACKS[square],
+    }
+}
+
+/// Follows every ray from `square` up to, and including, the first occupied square
+fn slider_attacks(
+    rays: &[([Bitboard; SQUARES], bool); 4],
+    square: usize,
+    occupied: Bitboard,
+) -> Bitboard {
+    let mut attacks = 0;
+    for (ray, ascending) in rays {
+        let mut ray_attacks = ray[square];
+        let blockers = ray_attacks & occupied;
+        if blockers != 0 {
+            let nearest = if *ascending {
+                blockers.trailing_zeros()
+            } else {
+                63 - blockers.leading_zeros()
+            };
+            // Everything behind the nearest blocker is hidden
+            ray_attacks ^= ray[nearest as usize];
+        }
+        attacks |= ray_attacks;
+    }
+    attacks
+}
+
+/// Squares attacked by a bishop on `square`, given the occupied squares of the board
+pub fn bishop_attacks(square: usize, occupied: Bitboard) -> Bitboard {
+    slider_attacks(&BISHOP_RAYS, square, occupied)
+}
+
+/// Squares attacked by a rook on `square`, given the occupied squares of the board
+pub fn rook_attacks(square: usize, occupied: Bitboard) -> Bitboard {
+    slider_attacks(&ROOK_RAYS, square, occupied)
+}
+
+/// Squares attacked by a queen on `square`, given the occupied squares of the board
+pub fn queen_attacks(square: usize, occupied: Bitboard) -> Bitboard {
+    bishop_attacks(square, occupied) | rook_attacks(square, occupied)
+}
@@ -0,0 +1,93 @@
+//! Chess960, also known as Fischer Random Chess: the pieces of the first rank start in one of 960
+//! arrangements, with the bishops on squares of opposite colors and the king between the rooks.
+//!
+//! Castling works as usual once the game starts: the king lands on the g or c file and the rook
+//! next to it, wherever they started. See [ChessBoard::can_castle].
+
+use crate::fen::FENStringParsing;
+use crate::Color::{Black, White};
+use crate::PieceType::{Bishop, King, Knight, Queen, Rook};
+use crate::{ChessBoard, Piece, PieceType, BOARD_SIZE};
+
+/// The number of starting positions
+const CHESS960_POSITIONS: u16 = 960;
+
+/// The files of the two knights among the five squares left after placing the bishops and the
+/// queen, for each of the 10 ways to place them
+const KNIGHT_FILES: [(usize, usize); 10] = [
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 2),
+    (1, 3),
+    (1, 4),
+    (2, 3),
+    (2, 4),
+    (3, 4),
+];
+
+/// The pieces of the first rank, from the a file to the h file, of the starting position numbered
+/// `index` by Scharnagl's scheme
+fn back_rank(index: u16) -> [PieceType; BOARD_SIZE] {
+    let mut rank: [Option<PieceType>; BOARD_SIZE] = [None; BOARD_SIZE];
+    let mut n = index as usize;
+
+    // The light-squared bishop on b, d, f or h, the dark-squared one on a, c, e or g
+    rank[2 * (n % 4) + 1] = Some(Bishop);
+    n /= 4;
+    rank[2 * (n % 4)] = Some(Bishop);
+    n /= 4;
+
+    // The rest go on the free squares: the queen, the knights, and then rook, king and rook
+    let free = |rank: &[Option<PieceType>; BOARD_SIZE]| -> Vec<usize> {
+        (0..BOARD_SIZE).filter(|&col| rank[col].is_none()).collect()
+    };
+    rank[free(&rank)[n % 6]] = Some(Queen);
+    n /= 6;
+    let (first, second) = KNIGHT_FILES[n];
+    let squares = free(&rank);
+    rank[squares[first]] = Some(Knight);
+    rank[squares[second]] = Some(Knight);
+    for (col, piece_type) in free(&rank).into_iter().zip([Rook, King, Rook]) {
+        rank[col] = Some(piece_type);
+    }
+
+    rank.map(|piece_type| piece_type.expect("Every square of the first rank is taken"))
+}
+
+impl ChessBoard {
+    /// The Chess960 starting position numbered `index`, from 0 to 959, as in Scharnagl's scheme.
+    /// Position 518 is the standard starting position. Returns `None` for any other number.
+    ///
+    /// ```
+    /// use ajedrez::{BoardAsFEN, ChessBoard};
+    ///
+    /// let board = ChessBoard::new_chess960(0).unwrap();
+    /// assert_eq!("bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1", board.as_fen());
+    /// assert!(board.chess960);
+    /// ```
+    pub fn new_chess960(index: u16) -> Option<ChessBoard> {
+        if index >= CHESS960_POSITIONS {
+            return None;
+        }
+        let pieces = back_rank(index);
+        let rank = |color| -> String {
+            pieces
+                .iter()
+                .map(|&piece_type| Piece::new(color, piece_type).as_fen())
+                .collect()
+        };
+        // With only two rooks on the first rank, KQkq names them
+        let fen = format!(
+            "{}/pppppppp/8/8/8/8/PPPPPPPP/{} w KQkq - 0 1",
+            rank(Black),
+            rank(White)
+        );
+        let mut board = fen
+            .parse_fen()
+            .expect("Chess960 starting positions are valid FEN");
+        board.chess960 = true;
+        Some(board)
+    }
+}
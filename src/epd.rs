@@ -0,0 +1,313 @@
+//! Extended Position Description: a position without move counters followed by operations, each
+//! an opcode with its operands and a semicolon, as used by test suites like WAC or STS.
+//!
+//! ```text
+//! 2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id "WAC.001";
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use pest::Parser;
+use pest_derive::Parser;
+use thiserror::Error;
+
+use crate::pgn::unescape_string;
+use crate::{to_san, BoardAsFEN, ChessBoard, FENStringParsing, Move, ParseError, SanError};
+
+#[derive(Parser)]
+#[grammar = "epd.pest"]
+struct EPDParser;
+
+/// Opcodes whose operands are moves in SAN
+const MOVE_OPCODES: [&str; 5] = ["am", "bm", "pm", "pv", "sm"];
+/// Opcodes whose operand is an integer
+const INTEGER_OPCODES: [&str; 8] = ["acd", "acn", "acs", "ce", "dm", "fmvn", "hmvc", "rc"];
+/// Opcodes whose operand is a string, besides the comments `c0` to `c9` and `v0` to `v9`
+const STRING_OPCODES: [&str; 3] = ["eco", "id", "nic"];
+
+/// Why an EPD line couldn't be read. Columns start at 1.
+#[derive(Debug, PartialEq, Eq, Error)]
+pub enum EpdError {
+    /// The text doesn't follow the EPD syntax
+    #[error("Invalid EPD at column {col}: {message}")]
+    Syntax { col: usize, message: String },
+    /// The four fields of the position don't make a valid FEN position
+    #[error("Invalid EPD position: {0}")]
+    InvalidPosition(#[from] ParseError),
+    /// An opcode appears more than once
+    #[error("The opcode {0} appears more than once")]
+    DuplicateOpcode(String),
+    /// The operands don't suit the opcode, e.g. a word for `acd`
+    #[error("Invalid operand {operand:?} for opcode {opcode}")]
+    InvalidOperand { opcode: String, operand: String },
+    /// A move operand can't be played on the position
+    #[error("Invalid move {san} for opcode {opcode}: {source}")]
+    InvalidMove {
+        opcode: String,
+        san: String,
+        source: SanError,
+    },
+}
+
+impl From<pest::error::Error<Rule>> for EpdError {
+    fn from(error: pest::error::Error<Rule>) -> Self {
+        let col = match error.line_col {
+            pest::error::LineColLocation::Pos((_, col)) => col,
+            pest::error::LineColLocation::Span((_, col), _) => col,
+        };
+        EpdError::Syntax {
+            col,
+            message: error.variant.message().to_string(),
+        }
+    }
+}
+
+/// The operands of an EPD operation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EpdOperand {
+    /// No operand, e.g. for `draw_offer`
+    None,
+    /// A number, e.g. for `acd` or `ce`
+    Integer(i64),
+    /// A string, e.g. for `id` or the comments `c0` to `c9`. Quotes and backslashes are escaped
+    /// with a backslash in EPD, as in PGN.
+    Text(String),
+    /// Moves, written in SAN, e.g. for `bm`. The moves of a predicted variation (`pv`) follow on
+    /// from each other, the others are all played from the position.
+    Moves(Vec<Move>),
+    /// The operands of an opcode this module doesn't know, as written
+    Tokens(Vec<String>),
+}
+
+/// A position with its EPD operations
+///
+/// ```
+/// use ajedrez::{Epd, EpdOperand};
+///
+/// let epd: Epd = "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id \"WAC.001\";"
+///     .parse()
+///     .unwrap();
+/// assert_eq!(Some("WAC.001"), epd.id());
+/// assert_eq!("Qg6", ajedrez::to_san(&epd.board, epd.best_moves()[0]));
+/// assert_eq!(Some(&EpdOperand::Text("WAC.001".to_string())), epd.operations.get("id"));
+/// ```
+#[derive(Clone)]
+pub struct Epd {
+    /// The position. Its move counters come from the `hmvc` and `fmvn` operations, if any.
+    pub board: ChessBoard,
+    /// The operands of each opcode, sorted by opcode
+    pub operations: BTreeMap<String, EpdOperand>,
+}
+
+impl Epd {
+    /// A position without operations
+    pub fn new(board: ChessBoard) -> Self {
+        Epd {
+            board,
+            operations: BTreeMap::new(),
+        }
+    }
+
+    /// Reads a line of EPD
+    pub fn parse(line: &str) -> Result<Epd, EpdError> {
+        let parsed_epd = EPDParser::parse(Rule::epd, line.trim())?
+            .next()
+            .expect("A parsed EPD line has a root pair");
+        let mut fields = Vec::new();
+        let mut operations = Vec::new();
+        for pair in parsed_epd.into_inner() {
+            match pair.as_rule() {
+                Rule::position_field => fields.push(pair.as_str()),
+                Rule::operation => {
+                    let mut inner = pair.into_inner();
+                    let opcode = inner.next().unwrap().as_str().to_string();
+                    let operands: Vec<(String, bool)> = inner
+                        .map(|operand| match operand.as_rule() {
+                            Rule::string_operand => {
+                                (unescape_string(operand.into_inner().as_str()), true)
+                            }
+                            _ => (operand.as_str().to_string(), false),
+                        })
+                        .collect();
+                    operations.push((opcode, operands));
+                }
+                _ => {}
+            }
+        }
+
+        // EPD positions are FEN strings without the move counters
+        let mut epd = Epd::new(format!("{} 0 1", fields.join(" ")).parse_fen()?);
+        for (opcode, operands) in operations {
+            if epd.operations.contains_key(&opcode) {
+                return Err(EpdError::DuplicateOpcode(opcode));
+            }
+            let operand = epd.operand(&opcode, operands)?;
+            epd.operations.insert(opcode, operand);
+        }
+
+        // The move counters, if they are given
+        for (opcode, counter) in [
+            ("hmvc", &mut epd.board.half_moves),
+            ("fmvn", &mut epd.board.full_moves),
+        ] {
+            if let Some(EpdOperand::Integer(value)) = epd.operations.get(opcode) {
+                *counter = u32::try_from(*value).map_err(|_| EpdError::InvalidOperand {
+                    opcode: opcode.to_string(),
+                    operand: value.to_string(),
+                })?;
+            }
+        }
+        Ok(epd)
+    }
+
+    /// The typed operand of an operation, the strings telling whether each operand was quoted
+    fn operand(&self, opcode: &str, operands: Vec<(String, bool)>) -> Result<EpdOperand, EpdError> {
+        let invalid = |operands: &[(String, bool)]| EpdError::InvalidOperand {
+            opcode: opcode.to_string(),
+            operand: operands
+                .iter()
+                .map(|(operand, _)| operand.as_str())
+                .collect::<Vec<_>>()
+                .join(" "),
+        };
+        let is_string_opcode = STRING_OPCODES.contains(&opcode)
+            || (opcode.len() == 2
+                && (opcode.starts_with('c') || opcode.starts_with('v'))
+                && opcode.as_bytes()[1].is_ascii_digit());
+
+        if MOVE_OPCODES.contains(&opcode) {
+            if operands.is_empty() {
+                return Err(invalid(&operands));
+            }
+            let mut board = self.board.clone();
+            let mut moves = Vec::new();
+            for (san, _) in &operands {
+                let mov = board
+                    .parse_san(san)
+                    .map_err(|source| EpdError::InvalidMove {
+                        opcode: opcode.to_string(),
+                        san: san.clone(),
+                        source,
+                    })?;
+                if opcode == "pv" {
+                    board.make_move(mov).expect("Moves read from SAN are legal");
+                }
+                moves.push(mov);
+            }
+            Ok(EpdOperand::Moves(moves))
+        } else if INTEGER_OPCODES.contains(&opcode) {
+            match operands.as_slice() {
+                [(number, false)] => number
+                    .parse()
+                    .map(EpdOperand::Integer)
+                    .map_err(|_| invalid(&operands)),
+                _ => Err(invalid(&operands)),
+            }
+        } else if is_string_opcode {
+            // Strings are quoted when they have spaces, but some files don't bother
+            let words: Vec<&str> = operands.iter().map(|(word, _)| word.as_str()).collect();
+            Ok(EpdOperand::Text(words.join(" ")))
+        } else {
+            // Anything else is guessed from the way it is written
+            Ok(match operands.as_slice() {
+                [] => EpdOperand::None,
+                [(text, true)] => EpdOperand::Text(text.clone()),
+                [(number, false)] if number.parse::<i64>().is_ok() => {
+                    EpdOperand::Integer(number.parse().unwrap())
+                }
+                _ => EpdOperand::Tokens(operands.into_iter().map(|(token, _)| token).collect()),
+            })
+        }
+    }
+
+    /// The identifier of the position, from the `id` opcode
+    pub fn id(&self) -> Option<&str> {
+        match self.operations.get("id") {
+            Some(EpdOperand::Text(id)) => Some(id),
+            _ => None,
+        }
+    }
+
+    /// The best moves of the position, from the `bm` opcode
+    pub fn best_moves(&self) -> &[Move] {
+        self.moves("bm")
+    }
+
+    /// The moves to avoid in the position, from the `am` opcode
+    pub fn avoid_moves(&self) -> &[Move] {
+        self.moves("am")
+    }
+
+    fn moves(&self, opcode: &str) -> &[Move] {
+        match self.operations.get(opcode) {
+            Some(EpdOperand::Moves(moves)) => moves,
+            _ => &[],
+        }
+    }
+
+    /// The position and its operations as a line of EPD, the operations sorted by opcode.
+    /// Moves that can't be played on the position have no SAN: the moves of an opcode are
+    /// written up to the first of them, and an opcode left without moves isn't written.
+    pub fn to_epd(&self) -> String {
+        let fen = self.board.as_fen();
+        let mut epd = fen.split(' ').take(4).collect::<Vec<_>>().join(" ");
+        for (opcode, operand) in &self.operations {
+            let operands = match operand {
+                EpdOperand::None => Vec::new(),
+                EpdOperand::Integer(number) => vec![number.to_string()],
+                EpdOperand::Text(text) => {
+                    let text = text.replace('\\', "\\\\").replace('"', "\\\"");
+                    vec![format!("\"{text}\"")]
+                }
+                EpdOperand::Moves(moves) => {
+                    let sans = self.move_sans(opcode, moves);
+                    if sans.is_empty() {
+                        continue;
+                    }
+                    sans
+                }
+                EpdOperand::Tokens(tokens) => tokens.clone(),
+            };
+            epd.push(' ');
+            epd.push_str(opcode);
+            for operand in operands {
+                epd.push(' ');
+                epd.push_str(&operand);
+            }
+            epd.push(';');
+        }
+        epd
+    }
+
+    /// The SAN of the moves of an opcode, up to the first move that can't be played
+    fn move_sans(&self, opcode: &str, moves: &[Move]) -> Vec<String> {
+        let mut board = self.board.clone();
+        let mut sans = Vec::new();
+        for &mov in moves {
+            let Some(legal) = board.find_legal_move(mov) else {
+                break;
+            };
+            sans.push(to_san(&board, legal));
+            if opcode == "pv" && board.make_move(legal).is_err() {
+                break;
+            }
+        }
+        sans
+    }
+}
+
+impl FromStr for Epd {
+    type Err = EpdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Epd::parse(s)
+    }
+}
+
+impl fmt::Display for Epd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_epd())
+    }
+}
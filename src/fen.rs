@@ -1,3 +1,4 @@
+use std::cmp::Ordering;
 use std::str::FromStr;
 
 use log::debug;
@@ -5,18 +6,51 @@ use pest::iterators::Pair;
 use pest::Parser;
 use pest_derive::Parser;
 
+use crate::bitboard::squares;
+use crate::ChessMove::{CastleKingside, CastleQueenside};
 use crate::Color::{Black, White};
 use crate::PieceType::{Bishop, King, Knight, Pawn, Queen, Rook};
-use crate::{fen, ChessBoard, Color, ParseError, Piece, BOARD_SIZE};
+use crate::{
+    castling_file_letter, fen, pos_to_str, ChessBoard, ChessMove, Color, ParseError, Piece,
+    BOARD_SIZE, DEFAULT_KINGSIDE_ROOK_COL, DEFAULT_KING_COL, DEFAULT_QUEENSIDE_ROOK_COL,
+    FILE_BASE_U8,
+};
 
-pub const INITIAL_FEN_BOARD: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 0";
+pub const INITIAL_FEN_BOARD: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
 
 #[derive(Parser)]
 #[grammar = "fen.pest"]
 struct FENParser;
 
 pub trait FENStringParsing {
+    /// Parses a FEN string. Every rank must have 8 squares and the en passant square must be on
+    /// the rank the side to move can capture on, but the position itself isn't checked: it may
+    /// lack kings, for instance.
     fn parse_fen(&self) -> Result<ChessBoard, ParseError>;
+
+    /// Parses a FEN string of a position that can arise in a game, see [ChessBoard::validate]
+    fn parse_fen_strict(&self) -> Result<ChessBoard, ParseError> {
+        let board = self.parse_fen()?;
+        board.validate()?;
+        Ok(board)
+    }
+
+    /// Like [FENStringParsing::parse_fen_strict], for a Chess960 position: the kings don't
+    /// have to start on the e-file. The board has [ChessBoard::chess960] set.
+    ///
+    /// ```
+    /// use ajedrez::FENStringParsing;
+    ///
+    /// let fen = "1r4kr/pppppppp/8/8/8/8/PPPPPPPP/RK4R1 w KQkq - 0 1";
+    /// assert!(fen.parse_fen_strict().is_err());
+    /// assert!(fen.parse_fen_strict_960().unwrap().chess960);
+    /// ```
+    fn parse_fen_strict_960(&self) -> Result<ChessBoard, ParseError> {
+        let mut board = self.parse_fen()?;
+        board.chess960 = true;
+        board.validate()?;
+        Ok(board)
+    }
 }
 
 trait ToPiece {
@@ -43,7 +77,7 @@ impl ToPiece for Pair<'_, Rule> {
                     "{:?} Cannot be converted to any chess piece",
                     self.as_rule()
                 );
-                return None;
+                None
             }
         }
     }
@@ -51,10 +85,10 @@ impl ToPiece for Pair<'_, Rule> {
 
 impl FENStringParsing for str {
     fn parse_fen(&self) -> Result<ChessBoard, ParseError> {
-        let parsed_fen = match FENParser::parse(fen::Rule::fen_board, &self) {
+        let parsed_fen = match FENParser::parse(fen::Rule::fen_board, self) {
             Ok(mut pairs) => pairs.next().unwrap(),
             Err(e) => {
-                eprintln!("Invalid FEN string {}", e);
+                debug!("Invalid FEN string {}", e);
                 return Err(ParseError::InvalidFENString);
             }
         };
@@ -63,23 +97,30 @@ impl FENStringParsing for str {
         for p0 in parsed_fen.into_inner() {
             match p0.as_rule() {
                 Rule::rank => {
+                    let rank = BOARD_SIZE - row;
                     let mut col = 0;
                     for p1 in p0.into_inner() {
                         let p2 = p1.into_inner().peek().unwrap();
-                        if p2.as_rule() == Rule::empty_squares {
+                        let (squares, piece) = if p2.as_rule() == Rule::empty_squares {
                             let blanks = p2
                                 .as_str()
                                 .parse::<usize>()
                                 .expect("Empty squares shouuld be a number between 1 and 8");
-                            for _ in 0..blanks {
-                                board.set_piece_0(row, col, None);
-                                col += 1;
-                            }
+                            (blanks, None)
                         } else {
-                            board.set_piece_0(row, col, p2.to_piece());
+                            (1, p2.to_piece())
+                        };
+                        if col + squares > BOARD_SIZE {
+                            return Err(ParseError::TooManySquaresInRank { rank });
+                        }
+                        for _ in 0..squares {
+                            board.set_piece_0(row, col, piece);
                             col += 1;
                         }
                     }
+                    if col < BOARD_SIZE {
+                        return Err(ParseError::TooFewSquaresInRank { rank });
+                    }
                 }
                 Rule::RANK_SEPARATOR => {
                     row += 1;
@@ -88,6 +129,18 @@ impl FENStringParsing for str {
                     board.active_color = Color::from_str(p0.as_str())
                         .expect("Active color should be either 'b' or 'w'");
                 }
+                Rule::castling => {
+                    // The pieces are already placed, so the letters can be matched to the rooks
+                    for p1 in p0.into_inner() {
+                        let (color, castle_type, rook_col) = castling_right(&board, &p1)?;
+                        if board.castling_rights.has(color, castle_type) {
+                            return Err(ParseError::InvalidCastlingRights);
+                        }
+                        board
+                            .castling_rights
+                            .set(color, castle_type, Some(rook_col));
+                    }
+                }
                 Rule::en_passant_square => {
                     board.passant_square = board.get_square_a(p0.as_str());
                 }
@@ -95,23 +148,208 @@ impl FENStringParsing for str {
                     board.half_moves = p0
                         .as_str()
                         .parse()
-                        .expect("Half moves should be an integer");
+                        .map_err(|_| ParseError::InvalidMoveCounter)?;
                 }
                 Rule::full_moves => {
                     board.full_moves = p0
                         .as_str()
                         .parse()
-                        .expect("Full moves should be an integer");
+                        .map_err(|_| ParseError::InvalidMoveCounter)?;
                 }
                 _ => {
                     debug!("Ignoring rule {:?}", p0.as_rule());
                 }
             }
         }
+        // The side to move captures en passant towards the opposite side
+        if let Some(square) = board.passant_square {
+            let capture_row = match board.active_color {
+                White => 2,
+                Black => 5,
+            };
+            if square.row != capture_row {
+                return Err(ParseError::InvalidEnPassant);
+            }
+        }
         Ok(board)
     }
 }
 
+/// The right of a letter of the castling field, with the file of its rook.
+///
+/// `K` and `Q` stand for the outermost rook on their side of the king, as in X-FEN, and the file
+/// letters of Shredder-FEN name the rook itself. When there is no such rook `K` and `Q` fall back
+/// to the h and a files, like in a plain FEN string.
+fn castling_right(
+    board: &ChessBoard,
+    right: &Pair<Rule>,
+) -> Result<(Color, ChessMove, usize), ParseError> {
+    let letter = right.as_str().chars().next().unwrap();
+    let color = if letter.is_ascii_uppercase() {
+        White
+    } else {
+        Black
+    };
+    match right.as_rule() {
+        Rule::white_king | Rule::black_king => {
+            let rook_col = outermost_rook(board, color, CastleKingside);
+            Ok((
+                color,
+                CastleKingside,
+                rook_col.unwrap_or(DEFAULT_KINGSIDE_ROOK_COL),
+            ))
+        }
+        Rule::white_queen | Rule::black_queen => {
+            let rook_col = outermost_rook(board, color, CastleQueenside);
+            Ok((
+                color,
+                CastleQueenside,
+                rook_col.unwrap_or(DEFAULT_QUEENSIDE_ROOK_COL),
+            ))
+        }
+        _ => {
+            let rook_col = (letter.to_ascii_lowercase() as u8 - FILE_BASE_U8) as usize;
+            match rook_col.cmp(&castling_king_col(board, color)) {
+                Ordering::Greater => Ok((color, CastleKingside, rook_col)),
+                Ordering::Less => Ok((color, CastleQueenside, rook_col)),
+                Ordering::Equal => Err(ParseError::InvalidCastlingRights),
+            }
+        }
+    }
+}
+
+/// The file of the king of `color` on its first rank, or the e file if it isn't there
+fn castling_king_col(board: &ChessBoard, color: Color) -> usize {
+    let row = back_rank(color);
+    let king = Some(Piece::new(color, King));
+    (0..BOARD_SIZE)
+        .find(|&col| board.piece_at((row, col)) == king)
+        .unwrap_or(DEFAULT_KING_COL)
+}
+
+/// The file of the rook of `color` on its first rank farthest from the king on the given side
+fn outermost_rook(board: &ChessBoard, color: Color, castle_type: ChessMove) -> Option<usize> {
+    let row = back_rank(color);
+    let king_col = castling_king_col(board, color);
+    let rook = Some(Piece::new(color, Rook));
+    let is_rook = |col: &usize| board.piece_at((row, *col)) == rook;
+    match castle_type {
+        CastleKingside => (king_col + 1..BOARD_SIZE).rev().find(is_rook),
+        _ => (0..king_col).find(is_rook),
+    }
+}
+
+fn back_rank(color: Color) -> usize {
+    match color {
+        White => BOARD_SIZE - 1,
+        Black => 0,
+    }
+}
+
+/// The castling field of a FEN string. Shredder-FEN writes the file of every rook, X-FEN only
+/// those that aren't the outermost on their side of the king.
+pub(crate) fn castling_field(board: &ChessBoard, shredder: bool) -> String {
+    if shredder {
+        return board.castling_rights.to_string();
+    }
+    let mut field = String::new();
+    for (color, castle_type, default_col, letter) in [
+        (White, CastleKingside, DEFAULT_KINGSIDE_ROOK_COL, 'K'),
+        (White, CastleQueenside, DEFAULT_QUEENSIDE_ROOK_COL, 'Q'),
+        (Black, CastleKingside, DEFAULT_KINGSIDE_ROOK_COL, 'k'),
+        (Black, CastleQueenside, DEFAULT_QUEENSIDE_ROOK_COL, 'q'),
+    ] {
+        let Some(rook_col) = board.castling_rights.rook_col(color, castle_type) else {
+            continue;
+        };
+        let outermost = match outermost_rook(board, color, castle_type) {
+            Some(col) => col == rook_col,
+            None => rook_col == default_col,
+        };
+        if outermost {
+            field.push(letter);
+        } else {
+            field.push(castling_file_letter(color, rook_col));
+        }
+    }
+    if field.is_empty() {
+        field.push('-');
+    }
+    field
+}
+
+impl ChessBoard {
+    /// Checks that the position can arise in a game: each side has one king, no pawn stands on
+    /// the first or last rank, the en passant square follows a two-square pawn move, the castling
+    /// rights match the kings and rooks on their starting squares (the king on the e file unless
+    /// the game is Chess960), and the side that just moved isn't in check.
+    ///
+    /// ```
+    /// use ajedrez::{Color, FENStringParsing, ParseError};
+    ///
+    /// let board = "8/8/8/8/8/8/8/4K3 w - - 0 1".parse_fen().unwrap();
+    /// assert_eq!(Err(ParseError::MissingKing(Color::Black)), board.validate());
+    /// ```
+    pub fn validate(&self) -> Result<(), ParseError> {
+        for color in [White, Black] {
+            match self.bitboard(King, color).count_ones() {
+                0 => return Err(ParseError::MissingKing(color)),
+                1 => {}
+                _ => return Err(ParseError::TooManyKings(color)),
+            }
+        }
+
+        let pawns = self.piece_bitboards[Pawn as usize];
+        let back_ranks = squares(pawns).any(|(row, _)| row == 0 || row == BOARD_SIZE - 1);
+        if back_ranks {
+            return Err(ParseError::PawnOnBackRank);
+        }
+
+        if let Some(square) = self.passant_square {
+            // The pawn that just moved stands in front of the square, and the square it came from
+            // is empty
+            let (pawn_row, origin_row) = match (self.active_color, square.row) {
+                (White, 2) => (3, 1),
+                (Black, 5) => (4, 6),
+                _ => return Err(ParseError::InvalidEnPassant),
+            };
+            let moved = Some(Piece::new(self.active_color.inverse(), Pawn));
+            if self.piece_at((pawn_row, square.col)) != moved
+                || self.piece_at((square.row, square.col)).is_some()
+                || self.piece_at((origin_row, square.col)).is_some()
+            {
+                return Err(ParseError::InvalidEnPassant);
+            }
+        }
+
+        for color in [White, Black] {
+            let rook = Some(Piece::new(color, Rook));
+            for castle_type in [CastleKingside, CastleQueenside] {
+                let Some(rook_col) = self.castling_rights.rook_col(color, castle_type) else {
+                    continue;
+                };
+                let Some((row, king_col)) = self.castling_king(color) else {
+                    return Err(ParseError::InvalidCastlingRights);
+                };
+                if self.piece_at((row, rook_col)) != rook
+                    || (castle_type == CastleKingside) != (rook_col > king_col)
+                {
+                    return Err(ParseError::InvalidCastlingRights);
+                }
+            }
+        }
+
+        let opponent = self.active_color.inverse();
+        let king = self
+            .find_king(opponent)
+            .expect("Both kings are on the board");
+        if self.is_square_attacked(king, self.active_color) {
+            return Err(ParseError::OpponentInCheck);
+        }
+        Ok(())
+    }
+}
+
 impl FromStr for ChessBoard {
     type Err = ParseError;
     fn from_str(s: &str) -> Result<Self, ParseError> {
@@ -120,44 +358,58 @@ impl FromStr for ChessBoard {
 }
 
 pub trait BoardAsFEN {
+    /// The position as a FEN string. Castling rights with rooks other than the outermost ones are
+    /// written with the file of the rook, as in X-FEN.
     fn as_fen(&self) -> String;
+
+    /// The position as a Shredder-FEN string, where castling rights are written with the files of
+    /// the rooks, e.g. "HAha" instead of "KQkq"
+    fn as_shredder_fen(&self) -> String;
 }
 
 impl BoardAsFEN for ChessBoard {
     fn as_fen(&self) -> String {
-        let mut fen_code = String::new();
-        for rank in 0..BOARD_SIZE {
-            let mut empty_squares = 0;
-            for file in 0..BOARD_SIZE {
-                let square = self.squares[rank][file];
-                if square.is_empty() {
-                    empty_squares += 1;
-                    continue;
-                }
-                if empty_squares > 0 {
-                    fen_code.push(char::from_digit(empty_squares, 10).unwrap());
-                    empty_squares = 0;
-                }
-                fen_code.push(square.as_fen())
+        fen_string(self, &self.get_castling_as_string())
+    }
+
+    fn as_shredder_fen(&self) -> String {
+        fen_string(self, &castling_field(self, true))
+    }
+}
+
+fn fen_string(board: &ChessBoard, castling: &str) -> String {
+    let mut fen_code = String::new();
+    for rank in 0..BOARD_SIZE {
+        let mut empty_squares = 0;
+        for file in 0..BOARD_SIZE {
+            let square = board.square_at((rank, file));
+            if square.is_empty() {
+                empty_squares += 1;
+                continue;
             }
             if empty_squares > 0 {
                 fen_code.push(char::from_digit(empty_squares, 10).unwrap());
+                empty_squares = 0;
             }
-            if rank < BOARD_SIZE - 1 {
-                fen_code.push('/');
-            }
+            fen_code.push(square.as_fen())
+        }
+        if empty_squares > 0 {
+            fen_code.push(char::from_digit(empty_squares, 10).unwrap());
+        }
+        if rank < BOARD_SIZE - 1 {
+            fen_code.push('/');
         }
-        fen_code.push_str(&*format!(
-            " {} {} {} {} {}",
-            self.active_color,
-            self.get_castling_as_string(),
-            match self.passant_square {
-                None => '-',
-                Some(_) => self.passant_square.unwrap().as_fen(),
-            },
-            self.half_moves,
-            self.full_moves
-        ));
-        fen_code
     }
+    fen_code.push_str(&format!(
+        " {} {} {} {} {}",
+        board.active_color,
+        castling,
+        match board.passant_square {
+            None => String::from("-"),
+            Some(square) => pos_to_str((square.row, square.col)),
+        },
+        board.half_moves,
+        board.full_moves
+    ));
+    fen_code
 }
@@ -0,0 +1,331 @@
+//! A game with its variations, kept as a tree of moves, and a [GameCursor] to walk and edit it.
+//!
+//! Every node but the root holds a move. The first child of a node is the main continuation, the
+//! other children are the variations: alternatives to the main continuation.
+//!
+//! ```
+//! use std::str::FromStr;
+//! use ajedrez::{FENStringParsing, GameTree, Move, INITIAL_FEN_BOARD};
+//!
+//! let mut tree = GameTree::new(INITIAL_FEN_BOARD.parse_fen().unwrap());
+//! let mut cursor = tree.cursor();
+//! cursor.add_move(Move::from_str("e2e4").unwrap()).unwrap();
+//! cursor.add_move(Move::from_str("e7e5").unwrap()).unwrap();
+//! cursor.back();
+//! // An alternative to 1... e5
+//! cursor.add_move(Move::from_str("c7c5").unwrap()).unwrap();
+//! cursor.goto_ply(1);
+//! assert_eq!(1, cursor.variations().len());
+//! assert!(cursor.enter_variation(0));
+//! assert_eq!("c5", cursor.node().san());
+//! ```
+
+use crate::pgn::{PgnError, PgnLine, PgnMove};
+use crate::{to_san, ChessBoard, ChessMoveError, Move, MoveUndo};
+
+/// A node of a [GameTree]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GameNode {
+    mov: Option<Move>,
+    san: String,
+    children: Vec<GameNode>,
+    /// Numeric Annotation Glyphs of the move
+    pub nags: Vec<u8>,
+    /// Comments after the move. On the root, the comments before the first move of the game.
+    pub comments: Vec<String>,
+    /// Comments before the move, when it starts a variation
+    pub starting_comments: Vec<String>,
+}
+
+impl GameNode {
+    fn new(mov: Move, san: String) -> Self {
+        GameNode {
+            mov: Some(mov),
+            san,
+            ..GameNode::default()
+        }
+    }
+
+    /// The move that leads to this node, `None` on the root
+    pub fn mov(&self) -> Option<Move> {
+        self.mov
+    }
+
+    /// The move in Standard Algebraic Notation, empty on the root
+    pub fn san(&self) -> &str {
+        &self.san
+    }
+
+    /// The main continuation followed by the variations
+    pub fn children(&self) -> &[GameNode] {
+        &self.children
+    }
+
+    /// Adds a line of the movetext, played from `board`, to the children of this node
+    fn add_pgn_line(&mut self, board: &ChessBoard, line: &PgnLine) -> Result<(), PgnError> {
+        let mut board = board.clone();
+        let mut node = self;
+        for (i, pgn_move) in line.moves.iter().enumerate() {
+            let mov = pgn_move.resolve(&board)?;
+            let mut child = GameNode::new(mov, to_san(&board, mov));
+            child.nags = pgn_move.nags.clone();
+            child.comments = pgn_move.comments.clone();
+            if i == 0 {
+                child.starting_comments = line.comments.clone();
+            }
+            node.children.push(child);
+            let index = node.children.len() - 1;
+            for variation in &pgn_move.variations {
+                node.add_pgn_line(&board, variation)?;
+            }
+            board
+                .make_move(mov)
+                .map_err(|source| pgn_move.invalid(&board, source))?;
+            node = &mut node.children[index];
+        }
+        Ok(())
+    }
+
+    /// The movetext line that starts with this node, the variations of its first move being
+    /// `alternatives`
+    fn pgn_line(&self, alternatives: &[GameNode]) -> PgnLine {
+        let mut line = PgnLine {
+            comments: self.starting_comments.clone(),
+            moves: Vec::new(),
+        };
+        let (mut node, mut alternatives) = (self, alternatives);
+        loop {
+            line.moves.push(PgnMove {
+                san: node.san.clone(),
+                nags: node.nags.clone(),
+                comments: node.comments.clone(),
+                variations: alternatives.iter().map(|alt| alt.pgn_line(&[])).collect(),
+                ..PgnMove::default()
+            });
+            match node.children.split_first() {
+                Some((main, rest)) => (node, alternatives) = (main, rest),
+                None => break,
+            }
+        }
+        line
+    }
+}
+
+/// A game from a starting position, with all its variations
+#[derive(Clone)]
+pub struct GameTree {
+    start: ChessBoard,
+    root: GameNode,
+}
+
+impl GameTree {
+    /// An empty game from the `start` position
+    pub fn new(start: ChessBoard) -> Self {
+        GameTree {
+            start,
+            root: GameNode::default(),
+        }
+    }
+
+    /// The tree of the moves of a PGN movetext, played from `start`
+    pub fn from_pgn_line(start: ChessBoard, line: &PgnLine) -> Result<Self, PgnError> {
+        let mut tree = GameTree::new(start);
+        tree.root.add_pgn_line(&tree.start, line)?;
+        // The comments before the game belong to the root
+        if let Some(first) = tree.root.children.first_mut() {
+            tree.root.comments = std::mem::take(&mut first.starting_comments);
+        } else {
+            tree.root.comments = line.comments.clone();
+        }
+        Ok(tree)
+    }
+
+    /// The tree as PGN movetext, ready for [crate::write_pgn]
+    pub fn to_pgn_line(&self) -> PgnLine {
+        let mut line = match self.root.children.split_first() {
+            Some((main, rest)) => main.pgn_line(rest),
+            None => PgnLine::default(),
+        };
+        line.comments = self.root.comments.clone();
+        line
+    }
+
+    /// The position before the first move
+    pub fn start(&self) -> &ChessBoard {
+        &self.start
+    }
+
+    /// The node before the first move
+    pub fn root(&self) -> &GameNode {
+        &self.root
+    }
+
+    /// The moves of the main line
+    pub fn mainline(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        let mut node = &self.root;
+        while let Some(main) = node.children.first() {
+            moves.extend(main.mov);
+            node = main;
+        }
+        moves
+    }
+
+    /// A cursor on the starting position
+    pub fn cursor(&mut self) -> GameCursor<'_> {
+        let board = self.start.clone();
+        GameCursor {
+            tree: self,
+            path: Vec::new(),
+            undos: Vec::new(),
+            board,
+        }
+    }
+}
+
+/// A position in a [GameTree], with the board after the moves that lead to it.
+///
+/// Moving the cursor makes and takes back moves on its board, so it never replays the game from
+/// the start.
+pub struct GameCursor<'a> {
+    tree: &'a mut GameTree,
+    /// The index of the child taken at every ply
+    path: Vec<usize>,
+    undos: Vec<MoveUndo>,
+    board: ChessBoard,
+}
+
+impl GameCursor<'_> {
+    /// The position at the cursor
+    pub fn board(&self) -> &ChessBoard {
+        &self.board
+    }
+
+    /// The number of half moves from the start of the game
+    pub fn ply(&self) -> usize {
+        self.path.len()
+    }
+
+    /// The node at the cursor
+    pub fn node(&self) -> &GameNode {
+        self.path
+            .iter()
+            .fold(&self.tree.root, |node, &i| &node.children[i])
+    }
+
+    /// The node at the cursor, to edit its comments and glyphs
+    pub fn node_mut(&mut self) -> &mut GameNode {
+        self.path
+            .iter()
+            .fold(&mut self.tree.root, |node, &i| &mut node.children[i])
+    }
+
+    /// The alternatives to the next move of the main continuation
+    pub fn variations(&self) -> &[GameNode] {
+        self.node().children.get(1..).unwrap_or_default()
+    }
+
+    /// Plays the child at `index`
+    fn enter(&mut self, index: usize) -> bool {
+        let Some(mov) = self.node().children.get(index).and_then(|c| c.mov) else {
+            return false;
+        };
+        let undo = self
+            .board
+            .make_move(mov)
+            .expect("The moves of the tree are legal");
+        self.undos.push(undo);
+        self.path.push(index);
+        true
+    }
+
+    /// Follows the main continuation. Returns `false` at the end of the line.
+    pub fn forward(&mut self) -> bool {
+        self.enter(0)
+    }
+
+    /// Takes back the last move. Returns `false` on the starting position.
+    pub fn back(&mut self) -> bool {
+        match self.undos.pop() {
+            Some(undo) => {
+                self.board.unmake_move(undo);
+                self.path.pop();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Goes back or forward along the current line until `ply` half moves have been played.
+    /// Returns `false`, at the end of the line, if the line is shorter.
+    pub fn goto_ply(&mut self, ply: usize) -> bool {
+        while self.ply() > ply {
+            self.back();
+        }
+        while self.ply() < ply {
+            if !self.forward() {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Plays the first move of a variation, see [GameCursor::variations]
+    pub fn enter_variation(&mut self, index: usize) -> bool {
+        self.enter(index + 1)
+    }
+
+    /// Plays a legal move. The move becomes a new variation unless it is already in the tree.
+    pub fn add_move(&mut self, mov: Move) -> Result<(), ChessMoveError> {
+        let legal = self
+            .board
+            .find_legal_move(mov)
+            .ok_or(ChessMoveError::IllegalMove)?;
+        let san = to_san(&self.board, legal);
+        let node = self.node_mut();
+        let index = match node.children.iter().position(|c| c.mov == Some(legal)) {
+            Some(index) => index,
+            None => {
+                node.children.push(GameNode::new(legal, san));
+                node.children.len() - 1
+            }
+        };
+        self.enter(index);
+        Ok(())
+    }
+
+    /// The ply where the variation holding the cursor branches off its parent line, `None` on
+    /// the main line
+    fn branch_ply(&self) -> Option<usize> {
+        self.path.iter().rposition(|&i| i != 0)
+    }
+
+    /// Makes the variation holding the cursor the main continuation of its parent line.
+    /// Returns `false` on the main line.
+    pub fn promote_variation(&mut self) -> bool {
+        let Some(ply) = self.branch_ply() else {
+            return false;
+        };
+        let index = self.path[ply];
+        let parent = self.path[..ply]
+            .iter()
+            .fold(&mut self.tree.root, |node, &i| &mut node.children[i]);
+        let variation = parent.children.remove(index);
+        parent.children.insert(0, variation);
+        // The former main continuation and the variations before this one move down by one
+        self.path[ply] = 0;
+        true
+    }
+
+    /// Removes the variation holding the cursor and moves the cursor to where it branched off.
+    /// Returns `false` on the main line.
+    pub fn delete_variation(&mut self) -> bool {
+        let Some(ply) = self.branch_ply() else {
+            return false;
+        };
+        let index = self.path[ply];
+        self.goto_ply(ply);
+        self.node_mut().children.remove(index);
+        true
+    }
+}
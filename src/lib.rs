@@ -44,7 +44,7 @@
 //!
 //! fn main() {
 //!     let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
-//!     let board = ChessBoard::from_fen(fen).unwrap();
+//!     let board: ChessBoard = fen.parse().unwrap();
 //!     println!("{}", board.as_str());
 //! }
 //! ```
@@ -53,7 +53,7 @@
 //! The library provides utilities to detect if a king is in check or checkmate.
 //!
 //! ```rust
-//! use ajedrez::{ChessBoard, Color};
+//! use ajedrez::{ChessBoard, Color, PieceType};
 //!
 //! fn main() {
 //!     let mut board = ChessBoard::new();
@@ -92,6 +92,33 @@
 //! }
 //! ```
 //!
+//! ### Legal Moves
+//! `legal_moves()` lists every legal move for the side to move, taking pins, checks and
+//! castling into account.
+//!
+//! ```rust
+//! use ajedrez::{FENStringParsing, INITIAL_FEN_BOARD};
+//!
+//! fn main() {
+//!     let board = INITIAL_FEN_BOARD.parse_fen().unwrap();
+//!     assert_eq!(board.legal_moves().len(), 20);
+//! }
+//! ```
+//!
+//! ### Perft
+//! `perft()` counts the positions reachable after a number of half-moves. It is the usual way
+//! to check a move generator against known results, `perft_divide()` splits the count by the
+//! first move. There is also a command line tool: `cargo run --release --bin perft 4`.
+//!
+//! ```rust
+//! use ajedrez::{FENStringParsing, INITIAL_FEN_BOARD};
+//!
+//! fn main() {
+//!     let board = INITIAL_FEN_BOARD.parse_fen().unwrap();
+//!     assert_eq!(board.perft(3), 8902);
+//! }
+//! ```
+//!
 //! ## Additional Notes
 //! - The library assumes a standard 8x8 chessboard.
 //! - It is designed to be extensible for custom rules or variants of chess.
@@ -99,7 +126,6 @@
 
 // Copyright ⓒ 2023-2025 Noe Nieto
 
-
 use std::collections::{BTreeSet, HashMap};
 use std::fmt;
 use std::ops::{Range, RangeInclusive};
@@ -108,14 +134,34 @@ use thiserror::Error;
 
 use colored::Colorize;
 
+use crate::bitboard::{
+    bishop_attacks, king_attacks, knight_attacks, pawn_attacks, queen_attacks, rook_attacks,
+    square_bit, square_index, squares, Bitboard, SQUARES,
+};
+pub use crate::epd::{Epd, EpdError, EpdOperand};
 pub use crate::fen::{BoardAsFEN, FENStringParsing, INITIAL_FEN_BOARD};
-pub use crate::pgn::{PGNGame, PieceMove};
+pub use crate::game_tree::{GameCursor, GameNode, GameTree};
+pub use crate::pgn::{
+    write_pgn, GamePly, PGNGame, PgnError, PgnLine, PgnMove, PgnReader, PgnRecord, PlyEvent,
+    PrettyPrinter, ReplayObserver, SEVEN_TAG_ROSTER,
+};
+pub use crate::san::{to_san, SanError};
+pub use crate::search::{evaluate, search, Score, SearchInfo, SearchLimits};
+use crate::zobrist::{castling_key, en_passant_key, piece_key, turn_key};
 use crate::ChessMove::{CastleKingside, CastleQueenside};
 use crate::Color::{Black, White};
 use crate::PieceType::{Bishop, King, Knight, Pawn, Queen, Rook};
 
+pub mod bitboard;
+mod chess960;
+mod epd;
 mod fen;
+mod game_tree;
 mod pgn;
+mod san;
+mod search;
+mod uci;
+pub mod zobrist;
 
 #[derive(Debug, PartialEq, Eq, Error)]
 pub enum ParseError {
@@ -135,20 +181,49 @@ pub enum ParseError {
     UselessMove,
     #[error("The algebraic position is invalid")]
     InvalidAlgebraicPosition,
+    #[error("The promotion piece is invalid")]
+    InvalidPromotionPiece,
+    #[error("The promotion piece is missing")]
+    MissingPromotionPiece,
+    #[error("Rank {rank} has more than 8 squares")]
+    TooManySquaresInRank { rank: usize },
+    #[error("Rank {rank} has fewer than 8 squares")]
+    TooFewSquaresInRank { rank: usize },
+    #[error("The move counters are invalid")]
+    InvalidMoveCounter,
+    #[error("The {0:?} king is missing")]
+    MissingKing(Color),
+    #[error("There is more than one {0:?} king")]
+    TooManyKings(Color),
+    #[error("There is a pawn on the first or last rank")]
+    PawnOnBackRank,
+    #[error("The en passant square doesn't follow a two-square pawn move")]
+    InvalidEnPassant,
+    #[error("The castling rights don't match the kings and rooks")]
+    InvalidCastlingRights,
+    #[error("The side that just moved is in check")]
+    OpponentInCheck,
 }
 
 pub const BOARD_SIZE: usize = 8;
 // Chessboard is 8x8
 pub const BOARD_SIZE_RANGE_0: Range<usize> = 0..BOARD_SIZE;
 const BOARD_SIZE_RANGE_1: RangeInclusive<usize> = 1..=BOARD_SIZE;
-const RANK_BASE_U8: u8 = '1' as u8;
-const FILE_BASE_U8: u8 = 'a' as u8;
+const RANK_BASE_U8: u8 = b'1';
+const FILE_BASE_U8: u8 = b'a';
 const RANK_UNICODE_USIZE_RANGE: RangeInclusive<usize> = 49..=56;
 const FILE_USIZE_RANGE: RangeInclusive<usize> = 97..=104;
-const FILE_CHAR_RANGE: RangeInclusive<char> = 'a'..='h';
 pub const DEFAULT_KING_COL: usize = 4;
 pub const DEFAULT_KINGSIDE_ROOK_COL: usize = 7;
 pub const DEFAULT_QUEENSIDE_ROOK_COL: usize = 0;
+/// Where the king and the rook land when castling kingside, wherever they started
+const KINGSIDE_KING_COL: usize = 6;
+const KINGSIDE_ROOK_COL: usize = 5;
+/// Where the king and the rook land when castling queenside, wherever they started
+const QUEENSIDE_KING_COL: usize = 2;
+const QUEENSIDE_ROOK_COL: usize = 3;
+/// The pieces a pawn may be promoted to, in the order moves are generated
+pub const PROMOTION_PIECES: [PieceType; 4] = [Queen, Rook, Bishop, Knight];
 
 /// The two different colors for the pieces
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -299,7 +374,7 @@ impl fmt::Display for Piece {
             Queen => "queen",
             King => "king",
         };
-        write!(f, "{} {}", self.color.to_string(), piece_name)
+        write!(f, "{} {}", self.color, piece_name)
     }
 }
 
@@ -438,8 +513,133 @@ pub struct CastlingStatus {
     pub check_empty_rows: bool,
 }
 
+/// The castling rights of both players, as written in the castling field of a FEN string.
+///
+/// Each right holds the file (zero-based column) of the rook the king may castle with, so
+/// positions where the rooks don't start on the a and h files can be represented.
+///
+/// A right only says that castling is still allowed *at some point* of the game: it is lost for
+/// good once the king or the rook moves (or the rook is captured), but having it doesn't mean that
+/// castling is possible right now. Use [ChessBoard::can_castle] for that.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CastlingRights {
+    /// The rook file White may castle **kingside** with
+    pub white_kingside: Option<usize>,
+    /// The rook file White may castle **queenside** with
+    pub white_queenside: Option<usize>,
+    /// The rook file Black may castle **kingside** with
+    pub black_kingside: Option<usize>,
+    /// The rook file Black may castle **queenside** with
+    pub black_queenside: Option<usize>,
+}
+
+impl CastlingRights {
+    /// Both players may castle on both sides with the rooks on the a and h files, i.e. "KQkq"
+    pub const ALL: CastlingRights = CastlingRights {
+        white_kingside: Some(DEFAULT_KINGSIDE_ROOK_COL),
+        white_queenside: Some(DEFAULT_QUEENSIDE_ROOK_COL),
+        black_kingside: Some(DEFAULT_KINGSIDE_ROOK_COL),
+        black_queenside: Some(DEFAULT_QUEENSIDE_ROOK_COL),
+    };
+
+    /// Nobody may castle, i.e. "-"
+    pub const NONE: CastlingRights = CastlingRights {
+        white_kingside: None,
+        white_queenside: None,
+        black_kingside: None,
+        black_queenside: None,
+    };
+
+    /// Tells whether `color` may castle to the given side. Anything other than `CastleKingside`
+    /// or `CastleQueenside` returns false.
+    pub fn has(&self, color: Color, castle_type: ChessMove) -> bool {
+        self.rook_col(color, castle_type).is_some()
+    }
+
+    /// The file of the rook `color` may castle with on the given side
+    pub fn rook_col(&self, color: Color, castle_type: ChessMove) -> Option<usize> {
+        match (color, castle_type) {
+            (White, CastleKingside) => self.white_kingside,
+            (White, CastleQueenside) => self.white_queenside,
+            (Black, CastleKingside) => self.black_kingside,
+            (Black, CastleQueenside) => self.black_queenside,
+            _ => None,
+        }
+    }
+
+    /// Sets the file of the rook `color` may castle with on the given side, `None` takes the
+    /// right away
+    pub fn set(&mut self, color: Color, castle_type: ChessMove, rook_col: Option<usize>) {
+        match (color, castle_type) {
+            (White, CastleKingside) => self.white_kingside = rook_col,
+            (White, CastleQueenside) => self.white_queenside = rook_col,
+            (Black, CastleKingside) => self.black_kingside = rook_col,
+            (Black, CastleQueenside) => self.black_queenside = rook_col,
+            _ => {}
+        }
+    }
+
+    /// Takes away the right of `color` to castle to the given side
+    pub fn remove(&mut self, color: Color, castle_type: ChessMove) {
+        self.set(color, castle_type, None);
+    }
+
+    /// Updates the rights after a rook left or got captured on `position`: the right to castle
+    /// with that rook is lost.
+    fn update(&mut self, position: (usize, usize)) {
+        for (color, row) in [(White, BOARD_SIZE - 1), (Black, 0)] {
+            for castle_type in [CastleKingside, CastleQueenside] {
+                if self.rook_col(color, castle_type).map(|col| (row, col)) == Some(position) {
+                    self.remove(color, castle_type);
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for CastlingRights {
+    /// Writes the rights in Shredder-FEN, with the file letter of every rook, e.g. "HAha", "Hb",
+    /// "GBgb" or "-". The `KQkq` letters of FEN and X-FEN depend on the other rooks of the
+    /// position, see [BoardAsFEN::as_fen].
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut s = String::new();
+        for (color, castle_type) in [
+            (White, CastleKingside),
+            (White, CastleQueenside),
+            (Black, CastleKingside),
+            (Black, CastleQueenside),
+        ] {
+            if let Some(col) = self.rook_col(color, castle_type) {
+                s.push(castling_file_letter(color, col));
+            }
+        }
+        if s.is_empty() {
+            s.push('-');
+        }
+        write!(f, "{}", s)
+    }
+}
+
+/// The file letter of a castling rook in Shredder-FEN and X-FEN: uppercase for White
+pub(crate) fn castling_file_letter(color: Color, col: usize) -> char {
+    let letter = (FILE_BASE_U8 + col as u8) as char;
+    match color {
+        White => letter.to_ascii_uppercase(),
+        Black => letter,
+    }
+}
+
+#[derive(Clone)]
 pub struct ChessBoard {
-    squares: [[Square; BOARD_SIZE]; BOARD_SIZE],
+    /// The piece on each square, indexed like the bits of a [Bitboard]. The pieces live here
+    /// (with their moves counters) and the bitboards below tell where they are.
+    pieces: [Option<Piece>; SQUARES],
+    /// The squares occupied by each color, indexed by [Color]
+    color_bitboards: [Bitboard; 2],
+    /// The squares occupied by each type of piece, indexed by [PieceType]
+    piece_bitboards: [Bitboard; 6],
+    /// The pieces part of the Zobrist key, see [ChessBoard::hash]
+    piece_hash: u64,
     /// Active Color: The next field indicates whose turn it is to move. "w" means it is White's
     /// turn, and "b" means it is Black's turn.
     pub active_color: Color,
@@ -454,6 +654,11 @@ pub struct ChessBoard {
     /// square is noted here. It's recorded using algebraic notation (e.g., "e3").
     /// If there's no en passant target square, this is represented by a dash "-".
     pub passant_square: Option<Square>,
+    /// Castling availability: who may still castle, and to which side
+    pub castling_rights: CastlingRights,
+    /// Whether the game is Chess960, where the king may castle from any file, see
+    /// [ChessBoard::new_chess960]
+    pub chess960: bool,
 
     /// Highlight specific squares. Useful for printing. (Move later to a display layer?)
     pub highlighted: HashMap<(usize, usize), Color>,
@@ -567,24 +772,103 @@ pub fn pos_from_str(s: &str) -> Result<(usize, usize), ParseError> {
     ))
 }
 
+/// Converts a zero-based `(row, col)` position into algebraic notation, i.e. the inverse of
+/// [pos_from_str]
+///
+/// ```
+/// use ajedrez::pos_to_str;
+/// assert_eq!(pos_to_str((7, 0)), "a1");
+/// assert_eq!(pos_to_str((2, 4)), "e6");
+/// ```
+pub fn pos_to_str(position: (usize, usize)) -> String {
+    format!(
+        "{}{}",
+        (FILE_BASE_U8 + position.1 as u8) as char,
+        BOARD_SIZE - position.0
+    )
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Move {
     pub from: (usize, usize),
     pub to: (usize, usize),
     pub castling: bool,
+    /// The piece a pawn turns into when it reaches the last rank
+    pub promotion: Option<PieceType>,
 }
 
 impl Move {
+    /// The null move, `0000` in UCI notation: the side to move passes. Engines report it when
+    /// they have no move to play.
+    pub const NULL: Move = Move {
+        from: (0, 0),
+        to: (0, 0),
+        castling: false,
+        promotion: None,
+    };
+
     /// The most usual way to create a move.
     pub fn new(from: (usize, usize), to: (usize, usize)) -> Self {
         Move {
             from,
             to,
             castling: false,
+            promotion: None,
+        }
+    }
+
+    /// Creates a pawn move that promotes to `piece_type` on the last rank.
+    pub fn new_promotion(from: (usize, usize), to: (usize, usize), piece_type: PieceType) -> Self {
+        Move {
+            promotion: Some(piece_type),
+            ..Move::new(from, to)
+        }
+    }
+
+    /// Tells whether this is [Move::NULL]
+    pub fn is_null(&self) -> bool {
+        *self == Move::NULL
+    }
+}
+
+impl fmt::Display for Move {
+    /// Writes the move in UCI notation, e.g. `e2e4`, `e7e8q` or `0000`. Castling is written as
+    /// the move of the king, see [ChessBoard::to_uci] for Chess960.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_null() {
+            return write!(f, "0000");
+        }
+        write!(f, "{}{}", pos_to_str(self.from), pos_to_str(self.to))?;
+        if let Some(promotion) = self.promotion {
+            write!(f, "{}", Piece::new(Black, promotion).as_fen())?;
         }
+        Ok(())
     }
 }
 
+/// Everything [ChessBoard::unmake_move] needs to take a move back, as returned by
+/// [ChessBoard::make_move].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MoveUndo {
+    /// The move that was made
+    pub mov: Move,
+    /// The moving piece as it was before the move, including its moves counter
+    pub moved: Piece,
+    /// The captured piece and the square it was taken from. For en passant captures this is not
+    /// the target square of the move.
+    pub captured: Option<(Piece, (usize, usize))>,
+    /// When castling: the rook as it was before the move, and the way it went
+    pub rook: Option<(Piece, Move)>,
+    /// The en passant target square before the move
+    pub passant_square: Option<Square>,
+    /// The castling rights before the move
+    pub castling_rights: CastlingRights,
+    /// The half-move clock before the move
+    pub half_moves: u32,
+    /// The full move counter before the move
+    pub full_moves: u32,
+}
+
 #[derive(Copy, Clone, PartialEq)]
 pub enum ChessMove {
     Simple,
@@ -592,13 +876,61 @@ pub enum ChessMove {
     CastleQueenside,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+/// The state of a game, as far as the rules of chess are concerned.
+///
+/// The fifty-move rule and the threefold repetition only allow a player to *claim* a draw, while
+/// the seventy-five-move rule and the fivefold repetition end the game automatically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameStatus {
+    /// The game goes on
+    Ongoing,
+    /// The side to move has been checkmated. The value is the color of the winner.
+    Checkmate(Color),
+    /// The side to move has no legal moves, but it is not in check
+    Stalemate,
+    /// No capture or pawn move in the last 50 moves (100 half-moves)
+    FiftyMoveRule,
+    /// No capture or pawn move in the last 75 moves (150 half-moves)
+    SeventyFiveMoveRule,
+    /// The current position appeared 3 times
+    ThreefoldRepetition,
+    /// The current position appeared 5 times
+    FivefoldRepetition,
+    /// Neither player has enough material left to checkmate
+    InsufficientMaterial,
+}
+
+impl GameStatus {
+    /// True for every status but `Ongoing`
+    pub fn is_over(&self) -> bool {
+        *self != GameStatus::Ongoing
+    }
+
+    /// True if the game is drawn (or a draw can be claimed)
+    pub fn is_draw(&self) -> bool {
+        !matches!(self, GameStatus::Ongoing | GameStatus::Checkmate(_))
+    }
+}
+
+/// The identity of a position for repetition purposes, see [ChessBoard::position_key]
+pub type PositionKey = u64;
+
+#[derive(Debug, PartialEq, Eq, Error)]
 pub enum ChessMoveError {
+    #[error("The move goes out of the board")]
     OutOfBounds,
+    #[error("There is no piece to move")]
     StartPieceMissing,
+    #[error("Castling is not allowed")]
     CastlingForbidden,
+    #[error("The piece belongs to the other player")]
     WrongPieceColor,
+    #[error("Several pieces can make the move")]
     TooManyPossibleMoves,
+    #[error("The move is illegal")]
+    IllegalMove,
+    #[error("The promotion is invalid")]
+    InvalidPromotion,
 }
 
 impl FromStr for Move {
@@ -606,7 +938,10 @@ impl FromStr for Move {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let sb = s.as_bytes();
-        if s.len() != 4 {
+        if s == "0000" {
+            return Ok(Move::NULL);
+        }
+        if s.len() != 4 && s.len() != 5 {
             return Err(ParseError::StringTooShort);
         }
 
@@ -631,6 +966,15 @@ impl FromStr for Move {
                 (sb[2] - FILE_BASE_U8) as usize,
             ),
             castling: false,
+            // Promotions are written as a fifth char, e.g. `e7e8q`
+            promotion: match sb.get(4).map(|c| c.to_ascii_lowercase()) {
+                None => None,
+                Some(b'q') => Some(Queen),
+                Some(b'r') => Some(Rook),
+                Some(b'b') => Some(Bishop),
+                Some(b'n') => Some(Knight),
+                Some(_) => return Err(ParseError::InvalidPromotionPiece),
+            },
         };
         if mov.from == mov.to {
             return Err(ParseError::UselessMove);
@@ -639,31 +983,105 @@ impl FromStr for Move {
     }
 }
 
+impl Default for ChessBoard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ChessBoard {
     pub fn new() -> Self {
-        let mut squares = [[Square::default(); BOARD_SIZE]; BOARD_SIZE];
-        // Each square obj knows it's location
-        for (row, rank) in BOARD_SIZE_RANGE_1.enumerate() {
-            for (col, file) in FILE_CHAR_RANGE.enumerate() {
-                squares[row][col] = Square {
-                    piece: None,
-                    rank,
-                    file,
-                    row,
-                    col,
-                };
-            }
-        }
         ChessBoard {
-            squares,
+            pieces: [None; SQUARES],
+            color_bitboards: [0; 2],
+            piece_bitboards: [0; 6],
+            piece_hash: 0,
             active_color: Color::White,
             full_moves: 0,
             half_moves: 0,
             passant_square: None,
+            castling_rights: CastlingRights::NONE,
+            chess960: false,
             highlighted: HashMap::new(),
         }
     }
 
+    /// The piece at the given zero-based position, if any
+    pub(crate) fn piece_at(&self, position: (usize, usize)) -> Option<Piece> {
+        self.pieces[square_index(position)]
+    }
+
+    /// Puts a piece at (or removes it from) the given position, keeping the bitboards up to date
+    pub(crate) fn put_piece(&mut self, position: (usize, usize), piece: Option<Piece>) {
+        let index = square_index(position);
+        let bit = square_bit(position);
+        if let Some(old) = self.pieces[index] {
+            self.color_bitboards[old.color as usize] &= !bit;
+            self.piece_bitboards[old.piece_type as usize] &= !bit;
+            self.piece_hash ^= piece_key(old, position);
+        }
+        if let Some(new) = piece {
+            self.color_bitboards[new.color as usize] |= bit;
+            self.piece_bitboards[new.piece_type as usize] |= bit;
+            self.piece_hash ^= piece_key(new, position);
+        }
+        self.pieces[index] = piece;
+    }
+
+    /// The square at the given zero-based position, with its piece
+    pub(crate) fn square_at(&self, position: (usize, usize)) -> Square {
+        let (row, col) = position;
+        Square {
+            piece: self.piece_at(position),
+            rank: BOARD_SIZE - row,
+            file: (FILE_BASE_U8 + col as u8) as char,
+            row,
+            col,
+        }
+    }
+
+    /// The squares occupied by the pieces of the given type and color
+    pub fn bitboard(&self, piece_type: PieceType, color: Color) -> Bitboard {
+        self.piece_bitboards[piece_type as usize] & self.color_bitboards[color as usize]
+    }
+
+    /// The squares occupied by the pieces of the given color
+    pub fn color_bitboard(&self, color: Color) -> Bitboard {
+        self.color_bitboards[color as usize]
+    }
+
+    /// The squares occupied by any piece
+    pub fn occupied(&self) -> Bitboard {
+        self.color_bitboards[0] | self.color_bitboards[1]
+    }
+
+    /// The pieces of the given color that attack `position`, whatever stands there
+    pub fn attackers(&self, position: (usize, usize), color: Color) -> Bitboard {
+        self.attackers_through(position, color, self.occupied())
+    }
+
+    /// Like [ChessBoard::attackers], with the sliding pieces blocked by `occupied` only
+    fn attackers_through(
+        &self,
+        position: (usize, usize),
+        color: Color,
+        occupied: Bitboard,
+    ) -> Bitboard {
+        let index = square_index(position);
+        let queens = self.bitboard(Queen, color);
+        // A pawn of `color` attacks this square from where an opposite pawn here would attack
+        (pawn_attacks(color.inverse(), index) & self.bitboard(Pawn, color))
+            | (knight_attacks(index) & self.bitboard(Knight, color))
+            | (king_attacks(index) & self.bitboard(King, color))
+            | (bishop_attacks(index, occupied) & (self.bitboard(Bishop, color) | queens))
+            | (rook_attacks(index, occupied) & (self.bitboard(Rook, color) | queens))
+    }
+
+    /// Tells whether any piece of the given color attacks `position`
+    pub fn is_square_attacked(&self, position: (usize, usize), color: Color) -> bool {
+        self.attackers(position, color) != 0
+    }
+
     pub fn set_piece(
         &mut self,
         rank: usize,
@@ -673,7 +1091,10 @@ impl ChessBoard {
     ) -> &mut ChessBoard {
         let index_rank = rank_to_index(rank);
         let index_file = c_file_to_index(file);
-        self.squares[index_rank][index_file].piece = Some(Piece::new(color, piece_type));
+        self.put_piece(
+            (index_rank, index_file),
+            Some(Piece::new(color, piece_type)),
+        );
         self
     }
 
@@ -686,170 +1107,26 @@ impl ChessBoard {
     ///     .set_piece(...);
     /// ```
     pub fn set_piece_0(&mut self, row: usize, col: usize, piece: Option<Piece>) -> &mut ChessBoard {
-        self.squares[row][col].piece = piece;
+        self.put_piece((row, col), piece);
         self
     }
 
     /// Gets the piece at the specified square using zero-based-index row and col
     pub fn get_piece_0(&self, row: usize, col: usize) -> Option<Piece> {
-        self.squares[row][col].piece
+        self.piece_at((row, col))
     }
 
     /// Gets the piece at the specified square using algebraic notation
     pub fn get_piece_a(&self, coordinate: &str) -> Option<Piece> {
-        match coordinate {
-            // File a
-            "a1" => self.squares[7][0].piece,
-            "a2" => self.squares[6][0].piece,
-            "a3" => self.squares[5][0].piece,
-            "a4" => self.squares[4][0].piece,
-            "a5" => self.squares[3][0].piece,
-            "a6" => self.squares[2][0].piece,
-            "a7" => self.squares[1][0].piece,
-            "a8" => self.squares[0][0].piece,
-            // file b
-            "b1" => self.squares[7][1].piece,
-            "b2" => self.squares[6][1].piece,
-            "b3" => self.squares[5][1].piece,
-            "b4" => self.squares[4][1].piece,
-            "b5" => self.squares[3][1].piece,
-            "b6" => self.squares[2][1].piece,
-            "b7" => self.squares[1][1].piece,
-            "b8" => self.squares[0][1].piece,
-            // File c
-            "c1" => self.squares[7][2].piece,
-            "c2" => self.squares[6][2].piece,
-            "c3" => self.squares[5][2].piece,
-            "c4" => self.squares[4][2].piece,
-            "c5" => self.squares[3][2].piece,
-            "c6" => self.squares[2][2].piece,
-            "c7" => self.squares[1][2].piece,
-            "c8" => self.squares[0][2].piece,
-            // File d
-            "d1" => self.squares[7][3].piece,
-            "d2" => self.squares[6][3].piece,
-            "d3" => self.squares[5][3].piece,
-            "d4" => self.squares[4][3].piece,
-            "d5" => self.squares[3][3].piece,
-            "d6" => self.squares[2][3].piece,
-            "d7" => self.squares[1][3].piece,
-            "d8" => self.squares[0][3].piece,
-            // File e
-            "e1" => self.squares[7][4].piece,
-            "e2" => self.squares[6][4].piece,
-            "e3" => self.squares[5][4].piece,
-            "e4" => self.squares[4][4].piece,
-            "e5" => self.squares[3][4].piece,
-            "e6" => self.squares[2][4].piece,
-            "e7" => self.squares[1][4].piece,
-            "e8" => self.squares[0][4].piece,
-            // File F
-            "f1" => self.squares[7][5].piece,
-            "f2" => self.squares[6][5].piece,
-            "f3" => self.squares[5][5].piece,
-            "f4" => self.squares[4][5].piece,
-            "f5" => self.squares[3][5].piece,
-            "f6" => self.squares[2][5].piece,
-            "f7" => self.squares[1][5].piece,
-            "f8" => self.squares[0][5].piece,
-            // File h
-            "g1" => self.squares[7][6].piece,
-            "g2" => self.squares[6][6].piece,
-            "g3" => self.squares[5][6].piece,
-            "g4" => self.squares[4][6].piece,
-            "g5" => self.squares[3][6].piece,
-            "g6" => self.squares[2][6].piece,
-            "g7" => self.squares[1][6].piece,
-            "g8" => self.squares[0][6].piece,
-            // File h
-            "h1" => self.squares[7][7].piece,
-            "h2" => self.squares[6][7].piece,
-            "h3" => self.squares[5][7].piece,
-            "h4" => self.squares[4][7].piece,
-            "h5" => self.squares[3][7].piece,
-            "h6" => self.squares[2][7].piece,
-            "h7" => self.squares[1][7].piece,
-            "h8" => self.squares[0][7].piece,
-            _ => None,
-        }
+        pos_from_str(coordinate)
+            .ok()
+            .and_then(|position| self.piece_at(position))
     }
 
     pub fn get_square_a(&self, coordinate: &str) -> Option<Square> {
-        match coordinate {
-            // File a
-            "a1" => Some(self.squares[7][0]),
-            "a2" => Some(self.squares[6][0]),
-            "a3" => Some(self.squares[5][0]),
-            "a4" => Some(self.squares[4][0]),
-            "a5" => Some(self.squares[3][0]),
-            "a6" => Some(self.squares[2][0]),
-            "a7" => Some(self.squares[1][0]),
-            "a8" => Some(self.squares[0][0]),
-            // file b
-            "b1" => Some(self.squares[7][1]),
-            "b2" => Some(self.squares[6][1]),
-            "b3" => Some(self.squares[5][1]),
-            "b4" => Some(self.squares[4][1]),
-            "b5" => Some(self.squares[3][1]),
-            "b6" => Some(self.squares[2][1]),
-            "b7" => Some(self.squares[1][1]),
-            "b8" => Some(self.squares[0][1]),
-            // File c
-            "c1" => Some(self.squares[7][2]),
-            "c2" => Some(self.squares[6][2]),
-            "c3" => Some(self.squares[5][2]),
-            "c4" => Some(self.squares[4][2]),
-            "c5" => Some(self.squares[3][2]),
-            "c6" => Some(self.squares[2][2]),
-            "c7" => Some(self.squares[1][2]),
-            "c8" => Some(self.squares[0][2]),
-            // File d
-            "d1" => Some(self.squares[7][3]),
-            "d2" => Some(self.squares[6][3]),
-            "d3" => Some(self.squares[5][3]),
-            "d4" => Some(self.squares[4][3]),
-            "d5" => Some(self.squares[3][3]),
-            "d6" => Some(self.squares[2][3]),
-            "d7" => Some(self.squares[1][3]),
-            "d8" => Some(self.squares[0][3]),
-            // File e
-            "e1" => Some(self.squares[7][4]),
-            "e2" => Some(self.squares[6][4]),
-            "e3" => Some(self.squares[5][4]),
-            "e4" => Some(self.squares[4][4]),
-            "e5" => Some(self.squares[3][4]),
-            "e6" => Some(self.squares[2][4]),
-            "e7" => Some(self.squares[1][4]),
-            "e8" => Some(self.squares[0][4]),
-            // File f
-            "f1" => Some(self.squares[7][5]),
-            "f2" => Some(self.squares[6][5]),
-            "f3" => Some(self.squares[5][5]),
-            "f4" => Some(self.squares[4][5]),
-            "f5" => Some(self.squares[3][5]),
-            "f6" => Some(self.squares[2][5]),
-            "f7" => Some(self.squares[1][5]),
-            "f8" => Some(self.squares[0][5]),
-            // File g
-            "g1" => Some(self.squares[7][6]),
-            "g2" => Some(self.squares[6][6]),
-            "g3" => Some(self.squares[5][6]),
-            "g4" => Some(self.squares[4][6]),
-            "g5" => Some(self.squares[3][6]),
-            "g6" => Some(self.squares[2][6]),
-            "g7" => Some(self.squares[1][6]),
-            "g8" => Some(self.squares[0][6]),
-            // File h
-            "h1" => Some(self.squares[7][7]),
-            "h2" => Some(self.squares[6][7]),
-            "h3" => Some(self.squares[5][7]),
-            "h4" => Some(self.squares[4][7]),
-            "h5" => Some(self.squares[3][7]),
-            "h6" => Some(self.squares[2][7]),
-            "h7" => Some(self.squares[1][7]),
-            "h8" => Some(self.squares[0][7]),
-            _ => None,
-        }
+        pos_from_str(coordinate)
+            .ok()
+            .map(|position| self.square_at(position))
     }
 
     // This indicates the castling rights for both White and Black. It uses the following characters:
@@ -867,29 +1144,14 @@ impl ChessBoard {
             white_queenside: self.can_castle(White, ChessMove::CastleQueenside, check_empty_rows),
             black_kingside: self.can_castle(Black, ChessMove::CastleKingside, check_empty_rows),
             black_queenside: self.can_castle(Black, ChessMove::CastleQueenside, check_empty_rows),
-            check_empty_rows: check_empty_rows,
+            check_empty_rows,
         }
     }
 
+    /// The castling rights as written in a FEN string, e.g. "KQkq" or "-". Rooks that aren't
+    /// the outermost on their side of the king are written with their file, as X-FEN does.
     pub fn get_castling_as_string(&self) -> String {
-        let castling = self.get_castling(false);
-        let mut s = String::new();
-        if castling.white_kingside {
-            s.push('K');
-        }
-        if castling.white_queenside {
-            s.push('Q');
-        }
-        if castling.black_kingside {
-            s.push('k');
-        }
-        if castling.black_queenside {
-            s.push('q');
-        }
-        if s.is_empty() {
-            s.push('-');
-        }
-        s
+        fen::castling_field(self, false)
     }
 
     /// Returns an ascii-art like string representation of the current state of the board.
@@ -898,9 +1160,9 @@ impl ChessBoard {
         b.push_str("    a   b   c   d   e   f   g   h\n");
         b.push_str("  ┌───┬───┬───┬───┬───┬───┬───┬───┐\n");
         for row in BOARD_SIZE_RANGE_0 {
-            b.push_str(&*format!("{} │", row));
+            b.push_str(&format!("{} │", row));
             for col in BOARD_SIZE_RANGE_0 {
-                let token = match self.squares[row][col].piece {
+                let token = match self.piece_at((row, col)) {
                     Some(piece) => {
                         if piece.color == White {
                             piece.to_unicode_symbol().to_string().yellow()
@@ -912,15 +1174,15 @@ impl ChessBoard {
                 };
                 if self.highlighted.contains_key(&(row, col)) {
                     if self.highlighted.get(&(row, col)).unwrap() == &White {
-                        b.push_str(&*format!(" {} │", token.black().on_yellow()));
+                        b.push_str(&format!(" {} │", token.black().on_yellow()));
                     } else {
-                        b.push_str(&*format!(" {} │", token.black().on_blue()));
+                        b.push_str(&format!(" {} │", token.black().on_blue()));
                     }
                 } else {
-                    b.push_str(&*format!(" {} │", token));
+                    b.push_str(&format!(" {} │", token));
                 };
             }
-            b.push_str(&*format!(" {}\n", BOARD_SIZE - row));
+            b.push_str(&format!(" {}\n", BOARD_SIZE - row));
             if row < 7 {
                 b.push_str("  ├───┼───┼───┼───┼───┼───┼───┼───┤\n")
             }
@@ -936,7 +1198,7 @@ impl ChessBoard {
         let (x, y) = position;
 
         // Get the pawn at the current position
-        let pawn = match self.squares[x][y].piece {
+        let pawn = match self.piece_at((x, y)) {
             Some(p) => p,
             None => return moves, // No pawn, so no moves.
         };
@@ -952,57 +1214,78 @@ impl ChessBoard {
         // Can only move forward within the RANK range
         let mut fwd = (x as isize + direction) as usize;
         if BOARD_SIZE_RANGE_0.contains(&fwd) {
-            if self.squares[fwd][y].is_empty() {
-                moves.push(Move {
-                    from: position,
-                    to: (fwd, y),
-                    castling: false,
-                });
+            if self.piece_at((fwd, y)).is_none() {
+                moves.push(Move::new(position, (fwd, y)));
             }
 
             // Capture diagonally, to the left, except for first file/column
-            if (y as isize - 1) > 0 {
+            if y > 0 {
                 let left = y - 1;
-                if BOARD_SIZE_RANGE_0.contains(&left) && !self.squares[fwd][left].is_empty() {
-                    moves.push(Move {
-                        from: position,
-                        to: (fwd, left),
-                        castling: false,
-                    });
+                if self.is_opponent_piece((fwd, left), pawn.color) {
+                    moves.push(Move::new(position, (fwd, left)));
                 }
             }
 
             // Capture diagonally, to the right, except for last file/column
             let right = y + 1;
-            if BOARD_SIZE_RANGE_0.contains(&right) && !self.squares[fwd][right].is_empty() {
-                moves.push(Move {
-                    from: position,
-                    to: (fwd, right),
-                    castling: false,
-                });
+            if BOARD_SIZE_RANGE_0.contains(&right)
+                && self.is_opponent_piece((fwd, right), pawn.color)
+            {
+                moves.push(Move::new(position, (fwd, right)));
             }
         }
 
         // Initial two-square move
         // Make sure to check the pawn is in the initial position and
-        // there's no piece two squares ahead.
+        // there's no piece one or two squares ahead.
         let initial_position = match pawn.color {
             Color::White => x == 6,
             Color::Black => x == 1,
         };
         if initial_position {
+            let skipped = fwd;
             direction *= 2;
             fwd = (x as isize + direction) as usize;
-            if self.squares[x][y].piece.unwrap().moves == 0 && self.squares[fwd][y].is_empty() {
-                moves.push(Move {
-                    from: position,
-                    to: (fwd, y),
-                    castling: false,
-                });
+            if pawn.moves == 0
+                && self.piece_at((skipped, y)).is_none()
+                && self.piece_at((fwd, y)).is_none()
+            {
+                moves.push(Move::new(position, (fwd, y)));
+            }
+        }
+
+        // En passant: the pawn that just made a two-square move can be captured as if it had moved
+        // only one square. The target is the square it skipped, which sits on the 6th rank for
+        // White and on the 3rd rank for Black.
+        if let Some(passant) = self.passant_square {
+            let (capture_row, passant_row) = match pawn.color {
+                Color::White => (x as isize - 1, 2),
+                Color::Black => (x as isize + 1, 5),
+            };
+            if passant.row == passant_row
+                && passant.row as isize == capture_row
+                && passant.col.abs_diff(y) == 1
+            {
+                moves.push(Move::new(position, (passant.row, passant.col)));
             }
         }
 
-        // TODO: en passant
+        // A pawn reaching the last rank must be promoted: one move per piece it may turn into
+        let last_row = if pawn.color == White {
+            0
+        } else {
+            BOARD_SIZE - 1
+        };
+        if moves.iter().any(|m| m.to.0 == last_row) {
+            moves = moves
+                .into_iter()
+                .flat_map(|m| {
+                    PROMOTION_PIECES
+                        .iter()
+                        .map(move |&piece_type| Move::new_promotion(m.from, m.to, piece_type))
+                })
+                .collect();
+        }
         moves
     }
 
@@ -1011,7 +1294,7 @@ impl ChessBoard {
         let mut moves = Vec::new();
 
         // Get the knight at the current position
-        let knight = match self.squares[x][y].piece {
+        let knight = match self.piece_at((x, y)) {
             Some(p) => p,
             None => return moves, // No knight, so no moves.
         };
@@ -1046,13 +1329,9 @@ impl ChessBoard {
             }
 
             // Verify that the square is either empty or occupied by an opponent's piece
-            let dest_square: Square = self.squares[new_x as usize][new_y as usize];
+            let dest_square: Square = self.square_at((new_x as usize, new_y as usize));
             if dest_square.is_empty() || dest_square.piece.unwrap().color != knight.color {
-                moves.push(Move {
-                    from: position,
-                    to: (new_x as usize, new_y as usize),
-                    castling: false,
-                });
+                moves.push(Move::new(position, (new_x as usize, new_y as usize)));
             }
         }
 
@@ -1062,7 +1341,7 @@ impl ChessBoard {
     pub fn generate_intrinsic_bishop_moves(&self, position: (usize, usize)) -> Vec<Move> {
         let mut moves = Vec::new();
         // Get the bishop at the current position
-        let bishop = match self.squares[position.0][position.1].piece {
+        let bishop = match self.piece_at(position) {
             Some(p) => p,
             None => return moves, // No bishop, so no moves.
         };
@@ -1090,25 +1369,17 @@ impl ChessBoard {
 
                 let current_position = (x, y);
 
-                match self.squares[x][y].piece {
+                match self.piece_at((x, y)) {
                     Some(piece) => {
                         // If there's a piece of the opposite color, it can be captured
                         if piece.color != bishop.color {
-                            moves.push(Move {
-                                from: position,
-                                to: current_position,
-                                castling: false,
-                            });
+                            moves.push(Move::new(position, current_position));
                         }
                         break; // Stop moving in this direction whether a piece was captured or it's blocked
                     }
                     None => {
                         // If the square is empty, it's a valid move
-                        moves.push(Move {
-                            from: position,
-                            to: current_position,
-                            castling: false,
-                        });
+                        moves.push(Move::new(position, current_position));
                     }
                 }
             }
@@ -1122,7 +1393,7 @@ impl ChessBoard {
         let mut moves = Vec::new();
 
         // Get the rook at the current position
-        let rook = match self.squares[x][y].piece {
+        let rook = match self.piece_at((x, y)) {
             Some(p) => p,
             None => return moves, // No rook, so no moves.
         };
@@ -1146,26 +1417,18 @@ impl ChessBoard {
                     break;
                 }
 
-                match self.squares[x][y].piece {
+                match self.piece_at((x, y)) {
                     Some(piece) => {
                         // If there's a piece of the opposite color, it can be captured
                         if piece.color != rook.color {
-                            moves.push(Move {
-                                from: position,
-                                to: (x, y),
-                                castling: false,
-                            });
+                            moves.push(Move::new(position, (x, y)));
                         }
                         // Whether it's a capture or not, the rook can't move past this piece
                         break;
                     }
                     None => {
                         // Add the move to the list if the square is empty
-                        moves.push(Move {
-                            from: position,
-                            to: (x, y),
-                            castling: false,
-                        });
+                        moves.push(Move::new(position, (x, y)));
                     }
                 }
             }
@@ -1178,7 +1441,7 @@ impl ChessBoard {
     /// piece color/capture, and empty squares.
     pub fn generate_intrinsic_king_moves(&self, position: (usize, usize)) -> Vec<Move> {
         let mut moves = Vec::new();
-        let king = match self.squares[position.0][position.1].piece {
+        let king = match self.piece_at(position) {
             Some(p) => p,
             None => return moves, // No king, so no moves.
         };
@@ -1211,25 +1474,17 @@ impl ChessBoard {
                 && new_y >= 0
                 && new_y < BOARD_SIZE as isize
             {
-                match self.squares[new_x as usize][new_y as usize].piece {
+                match self.piece_at((new_x as usize, new_y as usize)) {
                     Some(piece) => {
                         // If the square is occupied by an opponent's piece, it's a capture move
                         if piece.color != king.color {
-                            moves.push(Move {
-                                from: position,
-                                to: (new_x as usize, new_y as usize),
-                                castling: false,
-                            });
+                            moves.push(Move::new(position, (new_x as usize, new_y as usize)));
                         }
                         // Otherwise, the king cannot move into a square occupied by an allied piece
                     }
                     None => {
                         // If the square is unoccupied, it's a valid move
-                        moves.push(Move {
-                            from: position,
-                            to: (new_x as usize, new_y as usize),
-                            castling: false,
-                        });
+                        moves.push(Move::new(position, (new_x as usize, new_y as usize)));
                     }
                 }
             }
@@ -1244,7 +1499,7 @@ impl ChessBoard {
     pub fn generate_intrinsic_queen_moves(&self, position: (usize, usize)) -> Vec<Move> {
         let mut moves = Vec::new();
 
-        let queen = match self.squares[position.0][position.1].piece {
+        let queen = match self.piece_at(position) {
             Some(p) => p,
             None => return moves, // No queen, so no moves.
         };
@@ -1280,27 +1535,19 @@ impl ChessBoard {
                     break;
                 }
 
-                match self.squares[x][y].piece {
+                match self.piece_at((x, y)) {
                     Some(piece) => {
                         // If a piece is found on the path
                         if piece.color != queen.color {
                             // If the piece is of opposite color, it can be captured
-                            moves.push(Move {
-                                from: position,
-                                to: (x, y),
-                                castling: false,
-                            });
+                            moves.push(Move::new(position, (x, y)));
                         }
                         // Since a piece is on this square, the queen cannot move past; break the loop
                         break;
                     }
                     None => {
                         // No piece on the square, the queen can move here
-                        moves.push(Move {
-                            from: position,
-                            to: (x, y),
-                            castling: false,
-                        });
+                        moves.push(Move::new(position, (x, y)));
                     }
                 }
             }
@@ -1311,11 +1558,7 @@ impl ChessBoard {
 
     /// Generates the set of possible moves for a given position with the most basic constraints.
     pub fn generate_intrinsic_moves(&self, position: (usize, usize)) -> Vec<Move> {
-        match self.squares[position.0][position.1]
-            .piece
-            .unwrap()
-            .piece_type
-        {
+        match self.piece_at(position).unwrap().piece_type {
             Pawn => self.generate_intrinsic_pawn_moves(position),
             Knight => self.generate_intrinsic_knight_moves(position),
             Bishop => self.generate_intrinsic_bishop_moves(position),
@@ -1325,72 +1568,135 @@ impl ChessBoard {
         }
     }
 
+    /// Finds the legal move of a piece of type `piece_type` (of the active color) that lands on
+    /// `to_position`. Castling is not inferred here, use `castle()` instead.
     pub fn infer_move(&self, to_position: (usize, usize), piece_type: PieceType) -> Option<Move> {
-        // Loop over all squares of the board to find opponent pieces
-        for i in 0..BOARD_SIZE {
-            for j in 0..BOARD_SIZE {
-                if let Some(piece) = self.squares[i][j].piece {
-                    if piece.color == self.active_color && piece.piece_type == piece_type {
-                        // Generate moves for this piece
-                        for m in self.generate_intrinsic_moves((i, j)) {
-                            if m.to == to_position {
-                                return Some(m);
-                            }
-                        }
-                    }
+        self.legal_moves()
+            .into_iter()
+            .find(|m| !m.castling && m.to == to_position && self.square_at(m.from).is(piece_type))
+    }
+
+    /// Generates every strictly legal move for the side to move (`active_color`).
+    ///
+    /// The candidates come from the `generate_intrinsic_*` functions, plus castling. Any candidate
+    /// that would leave the own king in check is discarded.
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let color = self.active_color;
+        let mut board = self.clone();
+        let mut moves = Vec::new();
+        for position in squares(self.color_bitboard(color)) {
+            for m in self.generate_intrinsic_moves(position) {
+                if !board.leaves_king_in_check(m) {
+                    moves.push(m);
                 }
             }
         }
-        None
-    }
 
-    /// Returns a set of all targeted squares by all the pieces of the provided color
-    pub fn targeted_squares(&self, color: Color) -> BTreeSet<(usize, usize)> {
-        let mut squares = BTreeSet::new();
-        // Loop over all squares of the board to find opponent pieces
-        for i in 0..BOARD_SIZE {
-            for j in 0..BOARD_SIZE {
-                if let Some(piece) = self.squares[i][j].piece {
-                    if piece.color == color {
-                        // Generate moves for this piece
-                        for m in self.generate_intrinsic_moves((i, j)) {
-                            // A move that directly targets king's position
-                            // or a move that targets the boundaries of the king
-                            squares.insert(m.to);
-                        }
-                    }
+        // Castling: can_castle() already takes care of checks on the king's path
+        if let Some((row, king_col)) = self.castling_king(color) {
+            for (castle_type, to_col) in [
+                (CastleKingside, KINGSIDE_KING_COL),
+                (CastleQueenside, QUEENSIDE_KING_COL),
+            ] {
+                if self.can_castle(color, castle_type, true) {
+                    moves.push(Move {
+                        from: (row, king_col),
+                        to: (row, to_col),
+                        castling: true,
+                        promotion: None,
+                    });
                 }
             }
         }
-        squares
+        moves
     }
 
-    pub fn find_pieces(&self, piece_type: PieceType, color: Color) -> Vec<&Square> {
-        let mut squares: Vec<&Square> = Vec::new();
-        for i in 0..BOARD_SIZE {
-            for j in 0..BOARD_SIZE {
-                if let Some(piece) = self.squares[i][j].piece {
-                    if piece.color == color && piece.piece_type == piece_type {
-                        squares.push(&self.squares[i][j]);
-                    }
-                }
-            }
+    /// The legal move matching `mov`. Castling moves may not be flagged, so they are matched by
+    /// their squares, but an exact match comes first: in Chess960 the king may castle to a square
+    /// it could also step to, and the flag tells them apart.
+    pub(crate) fn find_legal_move(&self, mov: Move) -> Option<Move> {
+        let legal_moves = self.legal_moves();
+        legal_moves
+            .iter()
+            .find(|&&m| m == mov)
+            .or_else(|| {
+                legal_moves
+                    .iter()
+                    .find(|m| m.from == mov.from && m.to == mov.to && m.promotion == mov.promotion)
+            })
+            .copied()
+    }
+
+    /// Plays the move on the board, tests whether the king of the moving piece is in check and
+    /// takes the move back.
+    fn leaves_king_in_check(&mut self, mov: Move) -> bool {
+        if self.piece_at(mov.from).is_none() {
+            return false;
         }
-        squares
+        let undo = self.play(mov);
+        let in_check = match self.find_king(undo.moved.color) {
+            Some(king_position) => self.is_king_in_check(king_position),
+            None => false,
+        };
+        self.unmake_move(undo);
+        in_check
     }
 
-    pub fn find_king(&self, king_color: Color) -> Option<(i32, i32)> {
-        // Find the king's position
-        for i in 0..BOARD_SIZE {
-            for j in 0..BOARD_SIZE {
-                if let Some(piece) = self.squares[i][j].piece {
-                    if piece.color == king_color && piece.piece_type == PieceType::King {
-                        Some((i, j));
-                    }
-                }
+    /// Tells whether the move is a pawn capturing en passant, i.e. a pawn moving diagonally to the
+    /// (empty) en passant target square.
+    pub fn is_en_passant(&self, mov: Move) -> bool {
+        let is_pawn = self.square_at(mov.from).is(Pawn);
+        match self.passant_square {
+            Some(passant) => {
+                is_pawn
+                    && mov.from.1 != mov.to.1
+                    && (passant.row, passant.col) == mov.to
+                    && self.piece_at(mov.to).is_none()
             }
+            None => false,
         }
-        None
+    }
+
+    /// Returns true if there is a piece of the opposite color of `color` at `position`
+    fn is_opponent_piece(&self, position: (usize, usize), color: Color) -> bool {
+        match self.piece_at(position) {
+            Some(piece) => piece.color != color,
+            None => false,
+        }
+    }
+
+    /// Returns a set of all targeted squares by all the pieces of the provided color
+    ///
+    /// Pawns are special: they target both of their diagonal squares (whether there is a piece to
+    /// capture or not), but never the squares in front of them.
+    pub fn targeted_squares(&self, color: Color) -> BTreeSet<(usize, usize)> {
+        let occupied = self.occupied();
+        let own = self.color_bitboard(color);
+        let mut targeted = 0;
+        for position in squares(own) {
+            let index = square_index(position);
+            targeted |= match self.piece_at(position).unwrap().piece_type {
+                Pawn => pawn_attacks(color, index),
+                Knight => knight_attacks(index) & !own,
+                Bishop => bishop_attacks(index, occupied) & !own,
+                Rook => rook_attacks(index, occupied) & !own,
+                Queen => queen_attacks(index, occupied) & !own,
+                King => king_attacks(index) & !own,
+            };
+        }
+        squares(targeted).collect()
+    }
+
+    /// Returns the squares where the pieces of the given type and color are
+    pub fn find_pieces(&self, piece_type: PieceType, color: Color) -> Vec<Square> {
+        squares(self.bitboard(piece_type, color))
+            .map(|position| self.square_at(position))
+            .collect()
+    }
+
+    /// Returns the zero-based position of the king of the given color, if there is one.
+    pub fn find_king(&self, king_color: Color) -> Option<(usize, usize)> {
+        squares(self.bitboard(King, king_color)).next()
     }
 
     /// Generates a set of King moves, i.e. intrinsic moves minus the squares where the king can be
@@ -1398,67 +1704,32 @@ impl ChessBoard {
     /// the game is over.
     pub fn generate_constrained_king_moves(&mut self, position: (usize, usize)) -> Vec<Move> {
         let mut moves = Vec::new();
-        if let Some(king) = self.squares[position.0][position.1].piece {
+        if let Some(king) = self.piece_at(position) {
             // Ensure that the piece is a king
             if king.piece_type == PieceType::King {
-                let intrinsic = self
-                    .generate_intrinsic_king_moves(position)
-                    .iter()
-                    .map(|mov| mov.to)
-                    .collect::<BTreeSet<(usize, usize)>>();
-                let targeted = self.targeted_squares(if king.color == Color::White {
-                    Color::Black
-                } else {
-                    Color::Black
-                });
-                let constrained = intrinsic
-                    .difference(&targeted)
-                    .cloned()
-                    .collect::<Vec<(usize, usize)>>();
-                for pos in constrained {
-                    moves.push(Move {
-                        from: position,
-                        to: pos,
-                        castling: false,
-                    });
-                }
-
-                // if we still have moves left, remove the ones that would set the king into checkmate
-                let moves_copy = moves.clone();
-                moves.clear();
-                if moves_copy.len() > 0 {
-                    for m in moves_copy {
-                        // Move the king to the new possible position
-                        self.squares[m.from.0][m.from.1].piece = None;
-                        self.squares[m.to.0][m.to.1].piece = Some(king);
-
-                        // If the king is not in check, it is a good move
-                        let targeted = self.targeted_squares(if king.color == Color::White {
-                            Color::Black
-                        } else {
-                            Color::Black
-                        });
-                        if !targeted.contains(&position) {
-                            moves.push(m);
-                        }
-                        // Return the king to it's original position
-                        self.squares[m.to.0][m.to.1].piece = None;
-                        self.squares[m.from.0][m.from.1].piece = Some(king);
+                // Keep the moves after which the king is not in check
+                for m in self.generate_intrinsic_king_moves(position) {
+                    let undo = self.play(m);
+                    if !self.is_king_in_check(m.to) {
+                        moves.push(m);
                     }
+                    self.unmake_move(undo);
                 }
 
-                if self.can_castle(king.color, ChessMove::CastleKingside, false) {
+                if self.can_castle(king.color, ChessMove::CastleKingside, true) {
                     moves.push(Move {
                         from: position,
-                        to: (position.0, 6),
+                        to: (position.0, KINGSIDE_KING_COL),
                         castling: true,
+                        promotion: None,
                     })
                 }
-                if self.can_castle(king.color, CastleQueenside, false) {
+                if self.can_castle(king.color, CastleQueenside, true) {
                     moves.push(Move {
                         from: position,
-                        to: (position.0, 2),
+                        to: (position.0, QUEENSIDE_KING_COL),
                         castling: true,
+                        promotion: None,
                     })
                 }
             }
@@ -1467,20 +1738,236 @@ impl ChessBoard {
         moves
     }
 
+    /// Tells whether the king of the side to move is in check
+    pub fn is_check(&self) -> bool {
+        match self.find_king(self.active_color) {
+            Some(position) => self.is_king_in_check(position),
+            None => false,
+        }
+    }
+
+    /// The side to move is in check and has no legal moves
+    pub fn is_checkmate(&self) -> bool {
+        self.is_check() && self.legal_moves().is_empty()
+    }
+
+    /// The side to move is not in check, but has no legal moves
+    pub fn is_stalemate(&self) -> bool {
+        !self.is_check() && self.legal_moves().is_empty()
+    }
+
+    /// Tells whether neither side can possibly checkmate. These are the recognized cases:
+    ///
+    /// * King against king
+    /// * King and a single knight or bishop against king
+    /// * Kings and bishops only, with all the bishops on squares of the same color
+    pub fn is_insufficient_material(&self) -> bool {
+        let mut knights = 0;
+        let mut bishops_square_colors = Vec::new();
+        for i in 0..BOARD_SIZE {
+            for j in 0..BOARD_SIZE {
+                match self.piece_at((i, j)).map(|p| p.piece_type) {
+                    Some(Pawn) | Some(Rook) | Some(Queen) => return false,
+                    Some(Knight) => knights += 1,
+                    Some(Bishop) => bishops_square_colors.push((i + j) % 2),
+                    Some(King) | None => {}
+                }
+            }
+        }
+        match (knights, bishops_square_colors.len()) {
+            (0, 0) | (1, 0) | (0, 1) => true,
+            (0, _) => bishops_square_colors
+                .iter()
+                .all(|c| *c == bishops_square_colors[0]),
+            _ => false,
+        }
+    }
+
+    /// Returns the Zobrist key of the position, see [zobrist] for its layout.
+    ///
+    /// The pieces part of the key is kept up to date incrementally as pieces are put on and taken
+    /// off the board (by `make_move()`, `move_piece()`, `castle()`, `unmake_move()` ...). The side
+    /// to move, the castling rights and the en passant target are public fields, so they are
+    /// added here.
+    pub fn hash(&self) -> u64 {
+        let mut hash = self.piece_hash;
+        for color in [White, Black] {
+            for castle_type in [CastleKingside, CastleQueenside] {
+                if self.castling_rights.has(color, castle_type) {
+                    hash ^= castling_key(color, castle_type);
+                }
+            }
+        }
+        if let Some(passant) = self.passant_square {
+            // Only when a pawn of the side to move stands beside the pawn that moved two squares
+            let row = if self.active_color == White { 3 } else { 4 };
+            let pawns = self.bitboard(Pawn, self.active_color);
+            let capturers = [passant.col.wrapping_sub(1), passant.col + 1]
+                .into_iter()
+                .filter(|col| *col < BOARD_SIZE)
+                .any(|col| pawns & square_bit((row, col)) != 0);
+            if capturers {
+                hash ^= en_passant_key(passant.col);
+            }
+        }
+        if self.active_color == White {
+            hash ^= turn_key();
+        }
+        hash
+    }
+
+    /// Returns the key that identifies the current position when looking for repetitions: the
+    /// placement of the pieces, the side to move, the castling rights and the en passant target.
+    /// This is the Zobrist key given by [ChessBoard::hash].
+    pub fn position_key(&self) -> PositionKey {
+        self.hash()
+    }
+
+    /// Computes the status of the game.
+    ///
+    /// `history` holds the [ChessBoard::position_key] of every position reached *before* the
+    /// current one, it is used to detect repetitions. Pass an empty slice if you don't track it.
+    pub fn game_status(&self, history: &[PositionKey]) -> GameStatus {
+        if self.legal_moves().is_empty() {
+            return if self.is_check() {
+                GameStatus::Checkmate(self.active_color.inverse())
+            } else {
+                GameStatus::Stalemate
+            };
+        }
+
+        let key = self.position_key();
+        let repetitions = 1 + history.iter().filter(|k| **k == key).count();
+        if repetitions >= 5 {
+            GameStatus::FivefoldRepetition
+        } else if self.half_moves >= 150 {
+            GameStatus::SeventyFiveMoveRule
+        } else if self.is_insufficient_material() {
+            GameStatus::InsufficientMaterial
+        } else if repetitions >= 3 {
+            GameStatus::ThreefoldRepetition
+        } else if self.half_moves >= 100 {
+            GameStatus::FiftyMoveRule
+        } else {
+            GameStatus::Ongoing
+        }
+    }
+
+    /// Counts the leaf nodes of the tree of legal moves, `depth` half-moves deep. Comparing the
+    /// result with the well known counts of reference positions validates the move generator.
+    pub fn perft(&self, depth: u32) -> u64 {
+        self.clone().perft_nodes(depth)
+    }
+
+    /// Like [ChessBoard::perft], but the count is split by the first move. This helps to find
+    /// which move is miscounted when the total is wrong.
+    pub fn perft_divide(&self, depth: u32) -> Vec<(Move, u64)> {
+        if depth == 0 {
+            return Vec::new();
+        }
+        let mut board = self.clone();
+        self.legal_moves()
+            .into_iter()
+            .map(|m| {
+                let undo = board.play(m);
+                let nodes = board.perft_nodes(depth - 1);
+                board.unmake_move(undo);
+                (m, nodes)
+            })
+            .collect()
+    }
+
+    fn perft_nodes(&mut self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let moves = self.legal_moves();
+        // Leaves don't need to be played
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+        let mut nodes = 0;
+        for m in moves {
+            let undo = self.play(m);
+            nodes += self.perft_nodes(depth - 1);
+            self.unmake_move(undo);
+        }
+        nodes
+    }
+
     /// A function that determines if the king is in check at the current position
     pub fn is_king_in_check(&self, position: (usize, usize)) -> bool {
-        if let Some(king) = self.squares[position.0][position.1].piece {
+        if let Some(king) = self.piece_at(position) {
             // Ensure that the piece is a king
             if king.piece_type == PieceType::King {
-                let targeted = self.targeted_squares(king.color.inverse());
-                return targeted.contains(&position);
+                return self.is_square_attacked(position, king.color.inverse());
             }
         }
         false // The king is not in check.
     }
 
     /// Moves the piece and increments the movements counter
+    ///
+    /// This is [ChessBoard::make_move] plus a human readable description of the move and the
+    /// highlighting of the involved squares.
     pub fn move_piece(&mut self, mov: Move) -> Result<String, ChessMoveError> {
+        let undo = self.make_move(mov)?;
+        let (from_x, from_y) = mov.from;
+        let (to_x, to_y) = mov.to;
+        let color = undo.moved.color;
+
+        if color == White {
+            // Clear highlighted squares
+            self.highlighted.clear();
+        }
+
+        // Highlight the involved squares
+        self.highlighted.insert((from_x, from_y), color);
+        self.highlighted.insert((to_x, to_y), color);
+
+        if let Some((_, rook_move)) = undo.rook {
+            self.highlighted.insert(rook_move.from, color);
+            self.highlighted.insert(rook_move.to, color);
+            let msg = format!(
+                "castles {}",
+                if to_y == KINGSIDE_KING_COL {
+                    "kingside"
+                } else {
+                    "queenside"
+                }
+            );
+            return Ok(msg);
+        }
+
+        let mut action_str = match undo.captured {
+            Some((_, square)) if square != mov.to => format!(
+                "Pawn at ({}, {}) captures Pawn en passant at ({}, {})",
+                from_x, from_y, to_x, to_y
+            ),
+            Some((captured, _)) => format!(
+                "{:?} at ({}, {}) captures {:?} at ({}, {})",
+                undo.moved.piece_type, from_x, from_y, captured.piece_type, to_x, to_y
+            ),
+            None => format!(
+                "{:?} at ({}, {}) moves to ({}, {})",
+                undo.moved.piece_type, from_x, from_y, to_x, to_y
+            ),
+        };
+
+        if let Some(promotion) = mov.promotion {
+            action_str.push_str(&format!(" and promotes to {:?}", promotion));
+        }
+        Ok(action_str)
+    }
+
+    /// Makes the move and returns what is needed to take it back with
+    /// [ChessBoard::unmake_move].
+    ///
+    /// The move is validated the same way [ChessBoard::move_piece] does it: the moving piece must
+    /// belong to the side to move, promotions must be valid and castling must be allowed. It is
+    /// not checked whether the move leaves the own king in check, see
+    /// [ChessBoard::legal_moves] for that.
+    pub fn make_move(&mut self, mov: Move) -> Result<MoveUndo, ChessMoveError> {
         let (from_x, from_y) = mov.from;
         let (to_x, to_y) = mov.to;
 
@@ -1490,67 +1977,197 @@ impl ChessBoard {
             return Err(ChessMoveError::OutOfBounds);
         }
 
+        // Pieces can't stay where they are, except the king when castling in Chess960. This
+        // rules out the null move too.
+        if mov.from == mov.to && !mov.castling {
+            return Err(ChessMoveError::IllegalMove);
+        }
+
         // Ensure the start piece is not missing
-        if self.squares[from_x][from_y].is_empty() {
+        if self.piece_at((from_x, from_y)).is_none() {
             return Err(ChessMoveError::StartPieceMissing);
         }
 
         // Whose turn is it?
-        let piece = self.squares[from_x][from_y].piece.unwrap();
+        let piece = self.piece_at((from_x, from_y)).unwrap();
         if piece.color != self.active_color {
             return Err(ChessMoveError::WrongPieceColor);
         }
 
-        // Do we have capture?
-        let action_str = if !self.squares[to_x][to_y].is_empty() {
-            format!(
-                "{:?} at ({}, {}) captures {:?} at ({}, {})",
-                self.squares[from_x][from_y].piece.unwrap().piece_type,
-                from_x,
-                from_y,
-                self.squares[to_x][to_y].piece.unwrap().piece_type,
-                to_x,
-                to_y
-            )
+        if mov.castling {
+            // Castling moves the king and the rook, neither of them gets promoted
+            if mov.promotion.is_some() {
+                return Err(ChessMoveError::InvalidPromotion);
+            }
+            let castle_type = match to_y {
+                KINGSIDE_KING_COL => CastleKingside,
+                QUEENSIDE_KING_COL => CastleQueenside,
+                _ => return Err(ChessMoveError::CastlingForbidden),
+            };
+            if self.castling_king(piece.color) != Some(mov.from)
+                || !self.can_castle(piece.color, castle_type, true)
+            {
+                return Err(ChessMoveError::CastlingForbidden);
+            }
         } else {
-            format!(
-                "{:?} at ({}, {}) moves to ({}, {})",
-                self.squares[from_x][from_y].piece.unwrap().piece_type,
-                from_x,
-                from_y,
-                to_x,
-                to_y
-            )
+            // Pawns reaching the last rank must be promoted, and only them
+            let last_row = if piece.color == White {
+                0
+            } else {
+                BOARD_SIZE - 1
+            };
+            let must_promote = piece.piece_type == Pawn && to_x == last_row;
+            match mov.promotion {
+                None if must_promote => return Err(ChessMoveError::InvalidPromotion),
+                Some(_) if !must_promote => return Err(ChessMoveError::InvalidPromotion),
+                Some(King) | Some(Pawn) => return Err(ChessMoveError::InvalidPromotion),
+                _ => {}
+            }
+        }
+
+        Ok(self.play(mov))
+    }
+
+    /// Makes the move without any validation. The piece at `mov.from` must exist.
+    fn play(&mut self, mov: Move) -> MoveUndo {
+        let (from_x, from_y) = mov.from;
+        let (to_x, to_y) = mov.to;
+        let piece = self.piece_at((from_x, from_y)).unwrap();
+        let mut undo = MoveUndo {
+            mov,
+            moved: piece,
+            captured: None,
+            rook: None,
+            passant_square: self.passant_square,
+            castling_rights: self.castling_rights,
+            half_moves: self.half_moves,
+            full_moves: self.full_moves,
         };
 
+        if mov.castling {
+            // Castling moves the rook too. It is lifted before the king moves and put down after,
+            // since the king may land where the rook stood.
+            let (castle_type, default_rook_col, nw_rook_col) = if to_y == KINGSIDE_KING_COL {
+                (CastleKingside, DEFAULT_KINGSIDE_ROOK_COL, KINGSIDE_ROOK_COL)
+            } else {
+                (
+                    CastleQueenside,
+                    DEFAULT_QUEENSIDE_ROOK_COL,
+                    QUEENSIDE_ROOK_COL,
+                )
+            };
+            let rook_col = self
+                .castling_rights
+                .rook_col(piece.color, castle_type)
+                .unwrap_or(default_rook_col);
+            let rook = self.piece_at((from_x, rook_col)).unwrap();
+            self.put_piece((from_x, rook_col), None);
+            undo.rook = Some((rook, Move::new((from_x, rook_col), (from_x, nw_rook_col))));
+        }
+
+        // The pawn captured en passant sits beside the capturing one, not on the target square
+        let captured_at = if self.is_en_passant(mov) {
+            (from_x, to_y)
+        } else {
+            mov.to
+        };
+        // A castling king may stay where it is, which isn't a capture
+        if let Some(captured) = self.piece_at(captured_at).filter(|_| !mov.castling) {
+            undo.captured = Some((captured, captured_at));
+            self.put_piece((captured_at.0, captured_at.1), None);
+        }
+
+        // The half-move clock restarts after a capture or a pawn advance
+        if undo.captured.is_some() || piece.piece_type == Pawn {
+            self.half_moves = 0;
+        } else {
+            self.half_moves += 1;
+        }
+
         // Now move the piece
-        self.squares[to_x][to_y].piece = self.squares[from_x][from_y].piece;
-        self.squares[to_x][to_y].piece.unwrap().moves += 1;
-        self.squares[from_x][from_y].piece = None;
+        self.put_piece((from_x, from_y), None);
+        self.put_piece(
+            (to_x, to_y),
+            Some(Piece {
+                moves: piece.moves + 1,
+                piece_type: mov.promotion.unwrap_or(piece.piece_type),
+                ..piece
+            }),
+        );
+        if let Some((rook, rook_move)) = undo.rook {
+            self.put_piece(
+                rook_move.to,
+                Some(Piece {
+                    moves: rook.moves + 1,
+                    ..rook
+                }),
+            );
+        }
 
-        if self.squares[to_x][to_y].piece.unwrap().color == White {
-            // Clear highlighted squares
-            self.highlighted.clear();
+        // A two-square pawn move leaves the skipped square as the en passant target
+        self.passant_square = if piece.piece_type == Pawn && from_x.abs_diff(to_x) == 2 {
+            Some(self.square_at(((from_x + to_x) / 2, from_y)))
         } else {
-            // Increment full move only after the black moves
-            self.full_moves += 1;
+            None
+        };
+
+        // Moving the king or a rook, or capturing a rook, loses castling rights
+        if piece.piece_type == King {
+            self.castling_rights.remove(piece.color, CastleKingside);
+            self.castling_rights.remove(piece.color, CastleQueenside);
         }
+        self.castling_rights.update(mov.from);
+        self.castling_rights.update(mov.to);
 
-        // Highlight the involved squares
-        self.highlighted.insert((from_x, from_y), self.active_color);
-        self.highlighted.insert((to_x, to_y), self.active_color);
+        // Increment full move only after the black moves
+        if piece.color == Black {
+            self.full_moves += 1;
+        }
 
         // Set the next active color
         self.active_color = self.active_color.inverse();
-        Ok(action_str)
+        undo
+    }
+
+    /// Takes back a move made with [ChessBoard::make_move], restoring the board exactly as it was
+    /// before, move counters included. Moves must be taken back in the reverse order they were
+    /// made.
+    pub fn unmake_move(&mut self, undo: MoveUndo) {
+        let (from_x, from_y) = undo.mov.from;
+        let (to_x, to_y) = undo.mov.to;
+
+        // The king and the rook may swap squares when castling, so both are lifted first
+        self.put_piece((to_x, to_y), None);
+        if let Some((_, rook_move)) = undo.rook {
+            self.put_piece(rook_move.to, None);
+        }
+        self.put_piece((from_x, from_y), Some(undo.moved));
+        if let Some((captured, (x, y))) = undo.captured {
+            self.put_piece((x, y), Some(captured));
+        }
+        if let Some((rook, rook_move)) = undo.rook {
+            self.put_piece(rook_move.from, Some(rook));
+        }
+
+        self.passant_square = undo.passant_square;
+        self.castling_rights = undo.castling_rights;
+        self.half_moves = undo.half_moves;
+        self.full_moves = undo.full_moves;
+        self.active_color = self.active_color.inverse();
     }
 
     /// Analyzes the board to tell if the king at the given position can castle
     ///
+    /// The king castles from the e file or, in Chess960, from wherever it stands on its first
+    /// rank. Either way it lands on the g or c file, and the rook next to it on the f or d file.
+    ///
     /// ## Castling rules:
     ///
-    /// 1. Neither the king nor the chosen rook has previously moved during the game.
-    /// 2. There are no pieces between the king and the chosen rook.
+    /// 1. Neither the king nor the chosen rook has previously moved during the game. That is, the
+    ///    player still has the castling right (see [ChessBoard::castling_rights]) and the pieces'
+    ///    moves counters are zero.
+    /// 2. There are no pieces between the king and the chosen rook, nor on the squares they land
+    ///    on, which are always the g and f files kingside and the c and d files queenside.
     /// 3. The king is not currently in check.
     /// 4. The squares that the king passes over are not attacked by an enemy piece, nor is the square where the king lands.
     /// 5. The king does not pass through a square that is attacked by an enemy piece.
@@ -1559,7 +2176,7 @@ impl ChessBoard {
     ///
     /// * `color`: The player color. Either Black or White
     /// * `castle_type`: Either `ChessMove::CastleKingside` or `CastleQueenside`. If you pass something else the method
-    ///    will fail silently and return false.
+    ///   will fail silently and return false.
     /// * `check_empty_squares`: If false it will ignore rule number #2
     ///
     /// ## Return
@@ -1572,115 +2189,88 @@ impl ChessBoard {
         check_empty_squares: bool,
     ) -> bool {
         let row = if color == Color::Black { 0 } else { 7 };
-        let rook_col: usize;
-        let empty_squares: Vec<(usize, usize)>;
-        match castle_type {
-            ChessMove::CastleKingside => {
-                rook_col = DEFAULT_KINGSIDE_ROOK_COL;
-                empty_squares = vec![(row, DEFAULT_KING_COL + 1), (row, DEFAULT_KING_COL + 2)];
-            }
-            CastleQueenside => {
-                rook_col = DEFAULT_QUEENSIDE_ROOK_COL;
-                empty_squares = vec![
-                    (row, DEFAULT_KING_COL - 1),
-                    (row, DEFAULT_KING_COL - 2),
-                    (row, DEFAULT_KING_COL - 3),
-                ];
-            }
+        let (king_to, rook_to) = match castle_type {
+            CastleKingside => (KINGSIDE_KING_COL, KINGSIDE_ROOK_COL),
+            CastleQueenside => (QUEENSIDE_KING_COL, QUEENSIDE_ROOK_COL),
             _ => return false,
-        }
+        };
+        let Some(rook_col) = self.castling_rights.rook_col(color, castle_type) else {
+            return false;
+        };
+        let Some((_, king_col)) = self.castling_king(color) else {
+            return false;
+        };
+        // The squares the king and the rook travel over, landing squares included
+        let king_path = king_col.min(king_to)..=king_col.max(king_to);
+        let rook_path = rook_col.min(rook_to)..=rook_col.max(rook_to);
+        // In Chess960 the rook may stand on the way of an attack on the king's path, but it
+        // doesn't stay there
+        let occupied = self.occupied() & !square_bit((row, rook_col));
 
         if let (Some(king), Some(rook)) = (
-            self.squares[row][DEFAULT_KING_COL].piece,
-            self.squares[row][rook_col].piece,
+            self.piece_at((row, king_col)),
+            self.piece_at((row, rook_col)),
         ) {
-            let targeted = self.targeted_squares(king.color.inverse());
             // Ensure that the pieces are the right type ...
             return king.piece_type == PieceType::King && rook.piece_type == Rook
                 // ... and color
                 && rook.color == color && king.color == color
-                // ... the king and the kingside rook haven't moved
+                // ... the rook is on the side it castles to
+                && (castle_type == CastleKingside) == (rook_col > king_col)
+                // ... the king and the rook haven't moved
                 && king.moves == 0 && rook.moves == 0
-                // ... the squares between them are empty,
+                // ... nothing but them stands on their way,
                 && if check_empty_squares {
-                empty_squares.iter().all(|p| self.squares[p.0][p.1].is_empty())
+                king_path.clone().chain(rook_path).all(|col| {
+                    col == king_col || col == rook_col || self.piece_at((row, col)).is_none()
+                })
             } else { true }
-                && !targeted.contains(&(row, DEFAULT_KING_COL))
-                //  ... doesn't move through check, and isn't castling into check.
-                && ! empty_squares.iter().any(|p| targeted.contains(p));
+                //  ... isn't in check, doesn't move through check, and isn't castling into check.
+                && !king_path.into_iter().any(|col| self.attackers_through((row, col), color.inverse(), occupied) != 0);
         }
         false
     }
 
+    /// The square the king of `color` castles from: the e file of its first rank, or any file of
+    /// it in Chess960. `None` if the king isn't there.
+    pub(crate) fn castling_king(&self, color: Color) -> Option<(usize, usize)> {
+        let row = match color {
+            White => BOARD_SIZE - 1,
+            Black => 0,
+        };
+        let king = Some(Piece::new(color, King));
+        if self.chess960 {
+            (0..BOARD_SIZE)
+                .map(|col| (row, col))
+                .find(|&position| self.piece_at(position) == king)
+        } else {
+            Some((row, DEFAULT_KING_COL)).filter(|&position| self.piece_at(position) == king)
+        }
+    }
+
     /// Performs castling, constrained by the rules described on `can_castle()`
     pub fn castle(
         &mut self,
         color: Color,
         castle_type: ChessMove,
     ) -> Result<String, ChessMoveError> {
-        let row = match color {
-            White => 7,
-            Black => 0,
-        };
-
-        let king_col = DEFAULT_KING_COL;
-        let nw_king_col: usize;
-        let rook_col: usize;
-        let nw_rook_col: usize;
-
-        match castle_type {
-            ChessMove::CastleKingside => {
-                nw_king_col = 6;
-                rook_col = 7;
-                nw_rook_col = 5;
-            }
-            CastleQueenside => {
-                nw_king_col = 2;
-                rook_col = 0;
-                nw_rook_col = 3;
-            }
+        let nw_king_col = match castle_type {
+            ChessMove::CastleKingside => KINGSIDE_KING_COL,
+            CastleQueenside => QUEENSIDE_KING_COL,
             _ => {
                 return Err(ChessMoveError::CastlingForbidden);
             }
         };
 
-        if self.can_castle(color, castle_type, false) {
-            let mut king = self.squares[row][king_col].piece.unwrap();
-            king.moves += 1;
-            let mut rook = self.squares[row][rook_col].piece.unwrap();
-            rook.moves += 1;
-            self.squares[row][king_col].piece = None;
-            self.squares[row][nw_king_col].piece = Some(king);
-            self.squares[row][rook_col].piece = None;
-            self.squares[row][nw_rook_col].piece = Some(rook);
-
-            if color == White {
-                // Clear highlighted squares
-                self.highlighted.clear();
-            } else {
-                self.full_moves += 1;
-            }
-
-            // Highlight the involved squares
-            self.highlighted.insert((row, king_col), color);
-            self.highlighted.insert((row, nw_king_col), color);
-            self.highlighted.insert((row, rook_col), color);
-            self.highlighted.insert((row, nw_rook_col), color);
-
-            // Toggle the active color
-            self.active_color = self.active_color.inverse();
-
-            let msg = format!(
-                "castles {}",
-                if castle_type == CastleKingside {
-                    "kingside"
-                } else {
-                    "queenside"
-                }
-            );
-            return Ok(msg);
-        }
-
-        Err(ChessMoveError::CastlingForbidden)
+        let (row, king_col) = match self.castling_king(color) {
+            Some(king) if self.can_castle(color, castle_type, true) => king,
+            _ => return Err(ChessMoveError::CastlingForbidden),
+        };
+        self.move_piece(Move {
+            from: (row, king_col),
+            to: (row, nw_king_col),
+            castling: true,
+            promotion: None,
+        })
     }
 }
@@ -1,287 +1,948 @@
-use log::warn;
 use std::collections::HashMap;
-use std::fmt::Write;
+use std::io::{self, BufRead, BufReader, Read};
+use std::str::FromStr;
 
-use pest::iterators::Pair;
+use pest::error::LineColLocation;
+use pest::iterators::{Pair, Pairs};
 use pest::Parser;
 use pest_derive::Parser;
+use thiserror::Error;
 
 use crate::fen::INITIAL_FEN_BOARD;
-use crate::PieceType::{Bishop, King, Knight, Pawn, Queen, Rook};
 use crate::{
-    rank_to_index, ChessBoard, ChessMove, ChessMoveError, Color, FENStringParsing, File2Index,
-    Move, PieceType,
+    to_san, BoardAsFEN, ChessBoard, ChessMoveError, Color, FENStringParsing, GameTree, Move,
+    ParseError, Piece, PieceType, SanError,
 };
 
 #[derive(Parser)]
 #[grammar = "pgn.pest"]
 struct PGNParser;
 
-pub struct PieceMove<'a> {
-    piece: PieceType,
-    color: Color,
-    from_row: i8,
-    from_col: i8,
-    to_row: i8,
-    to_col: i8,
-    /// zero based index row disambiguator
-    row_disambiguator: i8,
-    /// zero based index column disambiguator
-    col_disambiguator: i8,
-
-    // Fields are useful for debugging purposes
-    #[allow(dead_code)]
-    rule: Rule,
-    #[allow(dead_code)]
-    as_str: &'a str,
-    #[allow(dead_code)]
-    move_ix: usize,
-}
-
-impl PieceMove<'_> {
-    pub fn from_rule(parsed_move: Pair<Rule>) -> PieceMove {
-        let mut mp = PieceMove {
-            piece: PieceType::Pawn,
-            color: Color::White,
-            from_row: -1,
-            from_col: -1,
-            to_row: -1,
-            to_col: -1,
-            row_disambiguator: -1,
-            col_disambiguator: -1,
-            rule: parsed_move.as_rule(),
-            as_str: parsed_move.as_str(),
-            move_ix: 0,
+/// Why a PGN game couldn't be loaded or replayed. Lines and columns start at 1.
+#[derive(Debug, PartialEq, Eq, Error)]
+pub enum PgnError {
+    /// The text doesn't follow the PGN syntax
+    #[error("Invalid PGN at line {line}, column {col}: {message}")]
+    Syntax {
+        line: usize,
+        col: usize,
+        message: String,
+    },
+    /// The position of the `FEN` tag is invalid
+    #[error("Invalid FEN tag at line {line}, column {col}: {source}")]
+    InvalidFen {
+        line: usize,
+        col: usize,
+        source: ParseError,
+    },
+    /// The move is well written, but it can't be played on the board
+    #[error("Move {move_number} ({san}) at line {line}, column {col} can't be played: {source}")]
+    InvalidMove {
+        line: usize,
+        col: usize,
+        move_number: usize,
+        san: String,
+        source: ChessMoveError,
+    },
+}
+
+impl PgnError {
+    fn syntax(pair: &Pair<Rule>, message: String) -> Self {
+        let (line, col) = pair.line_col();
+        PgnError::Syntax { line, col, message }
+    }
+}
+
+impl PgnError {
+    /// Moves the location of the error down by `lines`, for games that don't start at the top of
+    /// the input
+    fn shifted(self, lines: usize) -> Self {
+        match self {
+            PgnError::Syntax { line, col, message } => PgnError::Syntax {
+                line: line + lines,
+                col,
+                message,
+            },
+            PgnError::InvalidFen { line, col, source } => PgnError::InvalidFen {
+                line: line + lines,
+                col,
+                source,
+            },
+            PgnError::InvalidMove {
+                line,
+                col,
+                move_number,
+                san,
+                source,
+            } => PgnError::InvalidMove {
+                line: line + lines,
+                col,
+                move_number,
+                san,
+                source,
+            },
+        }
+    }
+}
+
+impl From<pest::error::Error<Rule>> for PgnError {
+    fn from(e: pest::error::Error<Rule>) -> Self {
+        let (line, col) = match e.line_col {
+            LineColLocation::Pos(pos) => pos,
+            LineColLocation::Span(start, _) => start,
         };
+        PgnError::Syntax {
+            line,
+            col,
+            message: e.variant.message().to_string(),
+        }
+    }
+}
+
+/// A move of the movetext with the annotations that follow it. Moves are equal when they read
+/// the same, wherever they were read.
+#[derive(Debug, Clone, Eq, Default)]
+pub struct PgnMove {
+    /// The move as written, e.g. `Nbd7`, `exf8=Q+` or `O-O`
+    pub san: String,
+    /// Numeric Annotation Glyphs. The `!`, `?`, `!!`, `??`, `!?` and `?!` suffixes are stored as
+    /// their equivalent glyphs `$1` to `$6`.
+    pub nags: Vec<u8>,
+    /// Comments after the move, without their braces and with their whitespace collapsed
+    pub comments: Vec<String>,
+    /// Alternatives to this move
+    pub variations: Vec<PgnLine>,
+    /// Where the move was read, counting lines and columns from 1
+    pub line: usize,
+    pub col: usize,
+}
+
+impl PartialEq for PgnMove {
+    fn eq(&self, other: &Self) -> bool {
+        self.san == other.san
+            && self.nags == other.nags
+            && self.comments == other.comments
+            && self.variations == other.variations
+    }
+}
+
+impl PgnMove {
+    /// The legal move of `board` written by this move
+    pub(crate) fn resolve(&self, board: &ChessBoard) -> Result<Move, PgnError> {
+        board.parse_san(&self.san).map_err(|e| {
+            let source = match e {
+                SanError::AmbiguousMove(_) => ChessMoveError::TooManyPossibleMoves,
+                _ => ChessMoveError::IllegalMove,
+            };
+            self.invalid(board, source)
+        })
+    }
+
+    /// The error of this move not being playable on `board`
+    pub(crate) fn invalid(&self, board: &ChessBoard, source: ChessMoveError) -> PgnError {
+        PgnError::InvalidMove {
+            line: self.line,
+            col: self.col,
+            move_number: board.full_moves as usize,
+            san: self.san.clone(),
+            source,
+        }
+    }
+}
+
+/// A sequence of moves: the main line of a game or one of its variations
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PgnLine {
+    /// Comments before the first move
+    pub comments: Vec<String>,
+    pub moves: Vec<PgnMove>,
+}
+
+/// The number of half moves played in a game before reaching `board`, counting from the usual
+/// starting position
+fn start_ply(board: &ChessBoard) -> usize {
+    let moves = 2 * (board.full_moves as usize).saturating_sub(1);
+    match board.active_color {
+        Color::White => moves,
+        Color::Black => moves + 1,
+    }
+}
+
+/// Whether the value of a `Variant` tag names Chess960, which some programs call Fischerandom
+fn is_chess960_variant(variant: &str) -> bool {
+    ["Chess960", "Fischerandom"]
+        .iter()
+        .any(|name| variant.eq_ignore_ascii_case(name))
+}
+
+/// The glyph of a move suffix annotation
+fn suffix_nag(suffix: &str) -> u8 {
+    match suffix {
+        "!" => 1,
+        "?" => 2,
+        "!!" => 3,
+        "??" => 4,
+        "!?" => 5,
+        _ => 6,
+    }
+}
+
+impl PgnLine {
+    /// The line of moves played from `board`, written in SAN
+    pub fn from_moves(board: &ChessBoard, moves: &[Move]) -> Result<PgnLine, ChessMoveError> {
+        let mut board = board.clone();
+        let mut line = PgnLine::default();
+        for mov in moves {
+            let legal = board
+                .find_legal_move(*mov)
+                .ok_or(ChessMoveError::IllegalMove)?;
+            line.moves.push(PgnMove {
+                san: to_san(&board, legal),
+                ..PgnMove::default()
+            });
+            board.make_move(legal)?;
+        }
+        Ok(line)
+    }
 
-        for part in parsed_move.into_inner().into_iter() {
-            match part.as_rule() {
-                Rule::piece => {
-                    mp.piece = match part.as_str() {
-                        "K" => King,
-                        "Q" => Queen,
-                        "R" => Rook,
-                        "B" => Bishop,
-                        "N" => Knight,
-                        _ => Pawn,
+    /// Builds a line from the elements of the movetext or of a variation. `ply` is the number
+    /// of half moves played before the first move of the line, see [start_ply].
+    fn from_pairs(elements: Pairs<Rule>, mut ply: usize) -> Result<PgnLine, PgnError> {
+        let mut line = PgnLine::default();
+        let mut move_number = None;
+        for element in elements {
+            match element.as_rule() {
+                Rule::move_number_indication => move_number = Some(element),
+                Rule::san_move => {
+                    // The move number, if any, must match the side to move
+                    if let Some(number) = move_number.take() {
+                        let text = number.as_str();
+                        let (digits, dots) = text.split_at(text.find('.').unwrap());
+                        let black = ply % 2 == 1;
+                        let expected =
+                            format!("{}{}", ply / 2 + 1, if black { "..." } else { "." });
+                        if digits.parse() != Ok(ply / 2 + 1) || (dots.len() > 1) != black {
+                            return Err(PgnError::syntax(
+                                &number,
+                                format!("Expected move number {expected}, found {text}"),
+                            ));
+                        }
                     }
+                    ply += 1;
+                    let (row, col) = element.line_col();
+                    line.moves.push(PgnMove {
+                        san: element.as_str().to_string(),
+                        line: row,
+                        col,
+                        ..PgnMove::default()
+                    });
                 }
-                Rule::disambiguator => {
-                    let d = part.as_str();
-                    let value = d.file_to_zero_base_index();
-                    if value.is_ok() {
-                        mp.col_disambiguator = d.file_to_zero_base_index().unwrap() as i8;
+                Rule::move_suffix | Rule::nag => {
+                    let nag = if element.as_rule() == Rule::move_suffix {
+                        suffix_nag(element.as_str())
                     } else {
-                        mp.row_disambiguator = d.file_to_zero_base_index().unwrap() as i8;
+                        element.as_str()[1..].parse().map_err(|_| {
+                            PgnError::syntax(&element, "Glyphs go from $0 to $255".to_string())
+                        })?
+                    };
+                    match line.moves.last_mut() {
+                        Some(mv) => mv.nags.push(nag),
+                        None => {
+                            return Err(PgnError::syntax(
+                                &element,
+                                "An annotation glyph must follow a move".to_string(),
+                            ))
+                        }
                     }
                 }
-                Rule::to_file => {
-                    mp.to_col = part.as_str().file_to_zero_base_index().unwrap() as i8;
+                Rule::move_comment | Rule::line_comment => {
+                    // Comments may be wrapped over several lines
+                    let text = element
+                        .into_inner()
+                        .next()
+                        .map(|text| {
+                            text.as_str()
+                                .split_whitespace()
+                                .collect::<Vec<_>>()
+                                .join(" ")
+                        })
+                        .unwrap_or_default();
+                    match line.moves.last_mut() {
+                        Some(mv) => mv.comments.push(text),
+                        None => line.comments.push(text),
+                    }
                 }
-                Rule::to_rank => {
-                    mp.to_row = rank_to_index(part.as_str().parse::<usize>().unwrap()) as i8;
+                Rule::variation => {
+                    let Some(mv) = line.moves.last_mut() else {
+                        return Err(PgnError::syntax(
+                            &element,
+                            "A variation must follow the move it replaces".to_string(),
+                        ));
+                    };
+                    // The variation replaces the last move
+                    mv.variations
+                        .push(PgnLine::from_pairs(element.clone().into_inner(), ply - 1)?);
                 }
+                _ => {}
+            }
+        }
+        Ok(line)
+    }
+}
 
-                _ => todo!("Unexpected rule!"),
+/// Removes the escaping backslashes of a tag value, or of an EPD string
+pub(crate) fn unescape_string(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            if let Some(escaped) = chars.next() {
+                unescaped.push(escaped);
             }
+        } else {
+            unescaped.push(c);
         }
-        mp
     }
+    unescaped
+}
+
+/// The name and value of a tag pair
+fn tag_pair(metadata_block: Pair<Rule>) -> (String, String) {
+    let mut inner_pairs = metadata_block.into_inner();
+    let key = inner_pairs.next().unwrap().as_str().to_string();
+    let value = inner_pairs.next().unwrap().as_str();
+    (key, unescape_string(&value[1..value.len() - 1]))
 }
 
-pub struct PGNGame<'a> {
+/// A half move of the main line of a [PGNGame]
+#[derive(Clone)]
+pub struct GamePly {
+    pub mov: Move,
+    /// The move in Standard Algebraic Notation, as written by [to_san]
+    pub san: String,
+    /// The position after the move
+    pub board: ChessBoard,
+}
+
+/// A PGN game. It owns its data, so the text it was parsed from can be dropped.
+#[derive(Clone)]
+pub struct PGNGame {
+    /// The position before the first move
     board: ChessBoard,
     metadata: HashMap<String, String>,
     game_result: String,
-    moves: Vec<Pair<'a, Rule>>,
+    movetext: PgnLine,
 }
 
-impl<'a> PGNGame<'a> {
-    pub fn new(pgn_str: &'a str) -> Option<PGNGame<'a>> {
-        let parsed_pgn = PGNParser::parse(Rule::game, &pgn_str)
-            .expect("Invalid PGN file") // unwrap the parse result
+impl PGNGame {
+    /// Parses a PGN game. Games with a `FEN` tag start from its position, and their move numbers
+    /// must follow on from it. A `Variant "Chess960"` tag makes the game Chess960, see
+    /// [ChessBoard::chess960]. The moves are not checked until the game is replayed with
+    /// [PGNGame::play].
+    pub fn new(pgn_str: &str) -> Result<PGNGame, PgnError> {
+        let parsed_pgn = PGNParser::parse(Rule::game, pgn_str)?
             .next()
-            .unwrap();
+            .expect("A parsed game has a root pair");
         let mut g = PGNGame {
             board: INITIAL_FEN_BOARD
                 .parse_fen()
                 .expect("Error parsing initial FEN board"),
             metadata: HashMap::new(),
             game_result: String::new(),
-            moves: Vec::new(),
+            movetext: PgnLine::default(),
         };
+        // Where the FEN tag is, to check its position once the variant is known
+        let mut fen_tag = None;
 
         for child_node in parsed_pgn.into_inner() {
             match child_node.as_rule() {
                 Rule::metadata_block => {
-                    let mut inner_pairs = child_node.into_inner();
-                    let key = inner_pairs.next().unwrap().as_str().to_string();
-                    let value = inner_pairs.next().unwrap().as_str().to_string();
+                    let (line, col) = child_node.line_col();
+                    let (key, value) = tag_pair(child_node);
+                    // Games from a custom position have a SetUp tag and the position in a FEN tag
+                    if key == "FEN" {
+                        g.board = value.parse_fen().map_err(|source| PgnError::InvalidFen {
+                            line,
+                            col,
+                            source,
+                        })?;
+                        fen_tag = Some((line, col));
+                    }
                     g.metadata.insert(key, value);
                 }
                 Rule::game_result => {
-                    let inner_pairs = child_node.into_inner();
-                    g.game_result = String::from(inner_pairs.as_str());
+                    g.game_result = child_node.as_str().to_string();
                 }
-                Rule::move_list => {
-                    g.moves.extend(child_node.into_inner());
-                }
-                _ => {
-                    println!("Unknown rule {:?}", child_node);
+                Rule::movetext => {
+                    g.movetext = PgnLine::from_pairs(child_node.into_inner(), start_ply(&g.board))?;
                 }
+                _ => {}
             }
         }
 
-        Some(g)
+        g.board.chess960 = g.tag("Variant").is_some_and(is_chess960_variant);
+        if let Some((line, col)) = fen_tag {
+            g.board
+                .validate()
+                .map_err(|source| PgnError::InvalidFen { line, col, source })?;
+        }
+        Ok(g)
     }
 
-    pub fn play(mut self) {
-        println!("---------------------------------------------");
-        println!("| Game metadata                              ");
-        for (key, value) in &self.metadata {
-            println!("| {: <20} | {: <10}", key, value);
+    /// The moves of the game with their comments, annotation glyphs and variations
+    pub fn movetext(&self) -> &PgnLine {
+        &self.movetext
+    }
+
+    /// Replays the main line, telling `observer` about every half move. Stops at the first move
+    /// that can't be played.
+    ///
+    /// ```
+    /// use ajedrez::{PGNGame, PlyEvent};
+    ///
+    /// let game: PGNGame = "1. e4 d5 2. exd5 Qxd5 *".parse().unwrap();
+    /// let mut captures = Vec::new();
+    /// game.replay(&mut |event: &PlyEvent| {
+    ///     if event.captured.is_some() {
+    ///         captures.push(event.san.clone());
+    ///     }
+    /// })
+    /// .unwrap();
+    /// assert_eq!(vec!["exd5", "Qxd5"], captures);
+    /// ```
+    pub fn replay(&self, observer: &mut impl ReplayObserver) -> Result<(), PgnError> {
+        observer.start(self);
+        let mut board = self.board.clone();
+        for (ply, mv) in self.movetext.moves.iter().enumerate() {
+            let mov = mv.resolve(&board)?;
+            let (move_number, color) = (board.full_moves, board.active_color);
+            let san = to_san(&board, mov);
+            let captured = if board.is_en_passant(mov) {
+                Some(Piece::new(color.inverse(), PieceType::Pawn))
+            } else {
+                board.piece_at(mov.to)
+            };
+            board
+                .move_piece(mov)
+                .map_err(|source| mv.invalid(&board, source))?;
+            observer.ply(&PlyEvent {
+                ply: ply + 1,
+                move_number,
+                color,
+                mov,
+                san,
+                fen: board.as_fen(),
+                captured,
+                check: board.is_check(),
+                board: &board,
+            });
         }
+        observer.end(self);
+        Ok(())
+    }
+
+    /// Replays the main line, printing the tags, every move and the board after it. Stops at
+    /// the first move that can't be played.
+    pub fn play(&self) -> Result<(), PgnError> {
+        self.replay(&mut PrettyPrinter::new(io::stdout()))
+    }
+
+    /// The tag pairs of the game
+    pub fn metadata(&self) -> &HashMap<String, String> {
+        &self.metadata
+    }
+
+    /// The value of a tag
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.metadata.get(name).map(String::as_str)
+    }
+
+    /// The result token at the end of the movetext: `1-0`, `0-1`, `1/2-1/2` or `*`
+    pub fn game_result(&self) -> &str {
+        &self.game_result
+    }
+
+    /// The position before the first move, from the `FEN` tag if there is one
+    pub fn start_position(&self) -> &ChessBoard {
+        &self.board
+    }
 
-        println!("---------------------------------------------");
-        println!("| Game starts!                               ");
-        for ix in 0..self.moves.len() {
-            // Access the full_move by index. Clone it to avoid borrowing issues.
-            let full_move = self.moves[ix].clone();
-            println!(
-                "{}",
-                self.process_move_pair(&full_move)
-                    .expect("Full move should be valid")
-            );
-            println!("{}", self.board.as_str());
+    /// Plays the main line and returns every half move with the position it leads to. Fails at
+    /// the first move that can't be played.
+    pub fn plies(&self) -> Result<Vec<GamePly>, PgnError> {
+        let mut plies = Vec::with_capacity(self.movetext.moves.len());
+        self.replay(&mut |event: &PlyEvent| {
+            plies.push(GamePly {
+                mov: event.mov,
+                san: event.san.clone(),
+                board: event.board.clone(),
+            })
+        })?;
+        Ok(plies)
+    }
+
+    /// The moves of the main line
+    pub fn moves(&self) -> Result<Vec<Move>, PgnError> {
+        Ok(self.plies()?.into_iter().map(|ply| ply.mov).collect())
+    }
+
+    /// The moves of the main line in Standard Algebraic Notation
+    pub fn san_moves(&self) -> Result<Vec<String>, PgnError> {
+        Ok(self.plies()?.into_iter().map(|ply| ply.san).collect())
+    }
+
+    /// The position at the end of the main line
+    pub fn final_position(&self) -> Result<ChessBoard, PgnError> {
+        Ok(self
+            .plies()?
+            .pop()
+            .map_or_else(|| self.board.clone(), |ply| ply.board))
+    }
+
+    /// The game as a tree that can be navigated and edited
+    pub fn tree(&self) -> Result<GameTree, PgnError> {
+        GameTree::from_pgn_line(self.board.clone(), &self.movetext)
+    }
+
+    /// Writes the game in PGN export format, see [write_pgn]
+    pub fn to_pgn(&self) -> String {
+        let tags: Vec<(String, String)> = self
+            .metadata
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        write_pgn(&tags, &self.movetext, &self.game_result)
+    }
+}
+
+/// The tags every exported game has, in their export order
+pub const SEVEN_TAG_ROSTER: [&str; 7] =
+    ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+/// The tags of games from a custom position, in their export order
+const SETUP_TAGS: [&str; 2] = ["SetUp", "FEN"];
+
+/// The longest line of exported movetext
+const PGN_LINE_WIDTH: usize = 80;
+
+/// Writes a game in PGN export format.
+///
+/// The Seven Tag Roster comes first, in its canonical order and with `?` placeholders for the
+/// missing tags, followed by the `SetUp` and `FEN` tags of games from a custom position and by the
+/// other tags sorted by name. The `Result` tag always matches `result`. The movetext is wrapped
+/// at 80 columns and numbers the moves from the initial position, or from the position of the
+/// `FEN` tag.
+///
+/// ```
+/// use ajedrez::{write_pgn, FENStringParsing, Move, PgnLine, INITIAL_FEN_BOARD};
+/// use std::str::FromStr;
+///
+/// let board = INITIAL_FEN_BOARD.parse_fen().unwrap();
+/// let moves = ["e2e4", "e7e5", "g1f3"].map(|m| Move::from_str(m).unwrap());
+/// let line = PgnLine::from_moves(&board, &moves).unwrap();
+/// let tags = [("White".to_string(), "Morphy, Paul".to_string())];
+/// let pgn = write_pgn(&tags, &line, "*");
+/// assert!(pgn.starts_with("[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]"));
+/// assert!(pgn.ends_with("[Result \"*\"]\n\n1. e4 e5 2. Nf3 *\n"));
+/// ```
+pub fn write_pgn(tags: &[(String, String)], movetext: &PgnLine, result: &str) -> String {
+    let tag = |name: &str| {
+        tags.iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    };
+    let mut pgn = String::new();
+    for name in SEVEN_TAG_ROSTER {
+        let value = match name {
+            "Result" => result,
+            "Date" => tag(name).unwrap_or("????.??.??"),
+            _ => tag(name).unwrap_or("?"),
+        };
+        write_tag(&mut pgn, name, value);
+    }
+    for name in SETUP_TAGS {
+        if let Some(value) = tag(name) {
+            write_tag(&mut pgn, name, value);
         }
+    }
+    let mut extra_tags: Vec<&(String, String)> = tags
+        .iter()
+        .filter(|(key, _)| {
+            !SEVEN_TAG_ROSTER.contains(&key.as_str()) && !SETUP_TAGS.contains(&key.as_str())
+        })
+        .collect();
+    extra_tags.sort();
+    for (key, value) in extra_tags {
+        write_tag(&mut pgn, key, value);
+    }
+    pgn.push('\n');
 
-        println!("---------------------------------------------");
-        println!("| Game Result: {}", self.game_result);
-        println!("---------------------------------------------");
-    }
-
-    pub fn process_move_pair(&mut self, move_pair: &Pair<Rule>) -> Result<String, ChessMoveError> {
-        let mut move_number: usize = 0;
-        let mut log_str = String::new();
-        for mv in move_pair.clone().into_inner() {
-            match mv.as_rule() {
-                Rule::move_number => {
-                    move_number = mv
-                        .as_str()
-                        .parse()
-                        .expect("move_number should be an integer");
-                    write!(log_str, "Move {move_number}: ").unwrap();
-                }
-                Rule::white_move => {
-                    write!(
-                        log_str,
-                        "White: {}",
-                        self.process_complete_move(move_number, Color::White, &mv)
-                            .unwrap()
-                    )
-                    .unwrap();
-                }
-                Rule::black_move => {
-                    write!(
-                        log_str,
-                        " | Black: {}",
-                        self.process_complete_move(move_number, Color::Black, &mv)
-                            .unwrap()
-                    )
-                    .unwrap();
-                }
-                _ => {
-                    warn!(
-                        "Ignoring rule {:?} while processing move_pair",
-                        mv.as_rule()
-                    )
-                }
-            }
+    // Games from a custom position are numbered from it
+    let ply = tag("FEN")
+        .and_then(|fen| fen.parse_fen().ok())
+        .map_or(0, |board| start_ply(&board));
+    let mut tokens = Vec::new();
+    movetext_tokens(movetext, ply, &mut tokens);
+    tokens.push(result.to_string());
+    let mut width = 0;
+    for token in tokens {
+        if width > 0 && width + 1 + token.len() > PGN_LINE_WIDTH {
+            pgn.push('\n');
+            width = 0;
+        } else if width > 0 {
+            pgn.push(' ');
+            width += 1;
         }
-        Ok(log_str)
-    }
-
-    pub fn process_complete_move(
-        &mut self,
-        move_ix: usize,
-        player_color: Color,
-        full_move: &Pair<Rule>,
-    ) -> Result<String, ChessMoveError> {
-        let move_or_castle = full_move
-            .clone()
-            .into_inner()
-            .next()
-            .expect("Unexpected empty rule pair");
-        match move_or_castle.as_rule() {
-            Rule::move_piece => {
-                let mut movement = PieceMove::from_rule(
-                    move_or_castle
-                        .into_inner()
-                        .next()
-                        .expect("Unexpected empty rule pair"),
-                );
-                movement.color = player_color;
-                movement.move_ix = move_ix;
-                return self.infer_move(&mut movement);
-            }
-            Rule::castle_kingside => {
-                return self.board.castle(player_color, ChessMove::CastleKingside)
-            }
-            Rule::castle_queenside => {
-                return self.board.castle(player_color, ChessMove::CastleQueenside)
+        width += token.len();
+        pgn.push_str(&token);
+    }
+    pgn.push('\n');
+    pgn
+}
+
+/// Writes a tag pair, escaping its value
+fn write_tag(pgn: &mut String, name: &str, value: &str) {
+    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+    pgn.push_str(&format!("[{name} \"{value}\"]\n"));
+}
+
+/// Adds the comments of a line to the movetext, one word per token so they can be wrapped
+fn comment_tokens(comment: &str, tokens: &mut Vec<String>) {
+    let words: Vec<&str> = comment.split_whitespace().collect();
+    match words.len() {
+        0 => tokens.push("{}".to_string()),
+        1 => tokens.push(format!("{{{}}}", words[0])),
+        n => {
+            tokens.push(format!("{{{}", words[0]));
+            tokens.extend(words[1..n - 1].iter().map(|w| w.to_string()));
+            tokens.push(format!("{}}}", words[n - 1]));
+        }
+    }
+}
+
+/// Adds the tokens of a line to the movetext. `ply` counts the half moves played before the
+/// first move of the line.
+fn movetext_tokens(line: &PgnLine, ply: usize, tokens: &mut Vec<String>) {
+    for comment in &line.comments {
+        comment_tokens(comment, tokens);
+    }
+    // Black moves are numbered at the start of a line and after comments and variations
+    let mut needs_number = true;
+    for (i, mv) in line.moves.iter().enumerate() {
+        let ply = ply + i;
+        // The number stays on the same line as its move
+        if ply.is_multiple_of(2) {
+            tokens.push(format!("{}. {}", ply / 2 + 1, mv.san));
+        } else if needs_number {
+            tokens.push(format!("{}... {}", ply / 2 + 1, mv.san));
+        } else {
+            tokens.push(mv.san.clone());
+        }
+        needs_number = false;
+        tokens.extend(mv.nags.iter().map(|nag| format!("${nag}")));
+        for comment in &mv.comments {
+            comment_tokens(comment, tokens);
+            needs_number = true;
+        }
+        for variation in &mv.variations {
+            let start = tokens.len();
+            movetext_tokens(variation, ply, tokens);
+            if tokens.len() == start {
+                tokens.push("()".to_string());
+            } else {
+                tokens[start].insert(0, '(');
+                tokens.last_mut().unwrap().push(')');
             }
-            _ => {}
+            needs_number = true;
         }
-        Ok(String::new())
+    }
+}
+
+/// A half move of a game replayed by [PGNGame::replay]
+pub struct PlyEvent<'a> {
+    /// The number of half moves played so far, counting this one
+    pub ply: usize,
+    /// The full move number of the move, as written in the movetext
+    pub move_number: u32,
+    /// The side that made the move
+    pub color: Color,
+    pub mov: Move,
+    /// The move in Standard Algebraic Notation
+    pub san: String,
+    /// The position after the move, as a FEN string
+    pub fen: String,
+    /// The piece taken by the move, if any
+    pub captured: Option<Piece>,
+    /// Whether the move gives check
+    pub check: bool,
+    /// The position after the move, with the squares of the move highlighted
+    pub board: &'a ChessBoard,
+}
+
+/// Receives the moves of a game replayed by [PGNGame::replay]. Closures taking a [PlyEvent] are
+/// observers too.
+pub trait ReplayObserver {
+    /// Called before the first move
+    fn start(&mut self, _game: &PGNGame) {}
+
+    /// Called after every half move of the main line
+    fn ply(&mut self, event: &PlyEvent);
+
+    /// Called after the last move, if every move could be played
+    fn end(&mut self, _game: &PGNGame) {}
+}
+
+impl<F: FnMut(&PlyEvent)> ReplayObserver for F {
+    fn ply(&mut self, event: &PlyEvent) {
+        self(event)
+    }
+}
+
+/// Writes a replayed game as a table of tags, then every move followed by the board, then the
+/// result. This is what [PGNGame::play] prints. Write errors are ignored.
+pub struct PrettyPrinter<W: io::Write> {
+    out: W,
+}
+
+impl<W: io::Write> PrettyPrinter<W> {
+    pub fn new(out: W) -> Self {
+        PrettyPrinter { out }
     }
 
-    fn infer_move(&mut self, movement: &mut PieceMove) -> Result<String, ChessMoveError> {
-        let mut available_pieces = self.board.find_pieces(movement.piece, movement.color);
+    /// Gives back the writer
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
 
-        // If we have a disambiguator, discard pieces that are not from that column
-        if movement.row_disambiguator >= 0 {
-            available_pieces.retain(|sq| sq.row == movement.row_disambiguator as usize);
+impl<W: io::Write> ReplayObserver for PrettyPrinter<W> {
+    fn start(&mut self, game: &PGNGame) {
+        let _ = writeln!(self.out, "---------------------------------------------");
+        let _ = writeln!(self.out, "| Game metadata                              ");
+        let mut tags: Vec<_> = game.metadata.iter().collect();
+        tags.sort();
+        for (key, value) in tags {
+            let _ = writeln!(self.out, "| {: <20} | {: <10}", key, value);
         }
-        if movement.col_disambiguator >= 0 {
-            available_pieces.retain(|sq| sq.col == movement.col_disambiguator as usize);
+        let _ = writeln!(self.out, "---------------------------------------------");
+        let _ = writeln!(self.out, "| Game starts!                               ");
+    }
+
+    fn ply(&mut self, event: &PlyEvent) {
+        let player = match event.color {
+            Color::White => "White",
+            Color::Black => "Black",
+        };
+        let _ = writeln!(
+            self.out,
+            "Move {}: {}: {}",
+            event.move_number, player, event.san
+        );
+        let _ = writeln!(self.out, "{}", event.board.as_str());
+    }
+
+    fn end(&mut self, game: &PGNGame) {
+        let _ = writeln!(self.out, "---------------------------------------------");
+        let _ = writeln!(self.out, "| Game Result: {}", game.game_result);
+        let _ = writeln!(self.out, "---------------------------------------------");
+    }
+}
+
+impl FromStr for PGNGame {
+    type Err = PgnError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PGNGame::new(s)
+    }
+}
+
+/// One game read by a [PgnReader]: its tag pairs and its movetext
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgnRecord {
+    /// The tag pairs in the order they appear, e.g. `("Event", "Casual game")`
+    pub tags: Vec<(String, String)>,
+    /// The moves, comments and result of the game
+    pub movetext: String,
+    /// The line of the input where the game starts, counting from 1
+    pub line: usize,
+    source: String,
+}
+
+impl PgnRecord {
+    /// The value of a tag
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The game, ready to be replayed. The lines of its errors are counted from the start of the
+    /// game, not from the start of the input.
+    pub fn game(&self) -> Result<PGNGame, PgnError> {
+        PGNGame::new(&self.source)
+    }
+}
+
+/// Reads the games of a PGN database one at a time, without loading the whole input.
+///
+/// A game ends with its result, or where the tags of the next game start. A game that can't be
+/// parsed is returned as an error and the reader carries on with the next game.
+///
+/// ```
+/// use ajedrez::PgnReader;
+///
+/// let pgn = "[Event \"One\"]\n\n1. e4 e5 1-0\n\n[Event \"Two\"]\n\n1. d4 Zz9 0-1\n";
+/// let games: Vec<_> = PgnReader::new(pgn.as_bytes()).collect();
+/// assert_eq!(2, games.len());
+/// assert_eq!(Some("One"), games[0].as_ref().unwrap().tag("Event"));
+/// assert!(games[1].is_err());
+/// ```
+pub struct PgnReader<R: Read> {
+    input: BufReader<R>,
+    /// The first line of the next game, read while looking for the end of the previous one
+    pending: Option<String>,
+    /// How many lines have been read so far
+    line: usize,
+    done: bool,
+}
+
+impl<R: Read> PgnReader<R> {
+    pub fn new(input: R) -> Self {
+        PgnReader {
+            input: BufReader::new(input),
+            pending: None,
+            line: 0,
+            done: false,
         }
+    }
 
-        // Fail early
-        if available_pieces.is_empty() {
-            return Err(ChessMoveError::StartPieceMissing);
+    /// The text of the next game and the line where it starts, if there are any games left. A
+    /// game ends with its result, or where the tags of the next game start.
+    fn next_game_text(&mut self) -> Option<(String, usize)> {
+        let mut text = String::new();
+        let mut start = self.line + 1;
+        let mut seen_event = false;
+        let mut seen_movetext = false;
+        // Whether the text read so far ends inside a `{` comment, which can span lines
+        let mut in_comment = false;
+        if let Some(line) = self.pending.take() {
+            start = self.line;
+            seen_event = line.trim_start().starts_with("[Event");
+            text.push_str(&line);
         }
 
-        // If we are down to one piece, we are done!
-        if available_pieces.len() == 1 {
-            movement.from_col = available_pieces[0].col as i8;
-            movement.from_row = available_pieces[0].row as i8;
-        } else {
-            // Ok we have more work left to be done.
-            // Gather all possible moves from the all the available pieces
-            let mut possible_moves: Vec<Move> = Vec::new();
-            for p in available_pieces {
-                let mut i_moves = self.board.generate_intrinsic_moves((p.row, p.col));
-                // Discard moves that don't go to our target square
-                i_moves.retain(|mv| {
-                    (mv.to.0, mv.to.1) == (movement.to_row as usize, movement.to_col as usize)
-                });
-                possible_moves.extend(i_moves.iter());
+        while !self.done {
+            let mut buf = Vec::new();
+            match self.input.read_until(b'\n', &mut buf) {
+                Ok(0) | Err(_) => self.done = true,
+                Ok(_) => {
+                    self.line += 1;
+                    let line = String::from_utf8_lossy(&buf).into_owned();
+                    let trimmed = line.trim_start();
+                    if !in_comment && trimmed.starts_with('%') {
+                        // Escaped lines are meant for other programs
+                        text.push('\n');
+                        continue;
+                    }
+                    let is_tag = !in_comment && is_tag_line(trimmed);
+                    let is_event = is_tag && trimmed.starts_with("[Event");
+                    if is_tag && (seen_movetext || (is_event && seen_event)) {
+                        self.pending = Some(line);
+                        break;
+                    }
+                    if text.trim().is_empty() {
+                        // Skip the blank lines between games
+                        start = self.line;
+                        text.clear();
+                    }
+                    seen_event |= is_event;
+                    text.push_str(&line);
+                    if !is_tag && !trimmed.is_empty() {
+                        seen_movetext = true;
+                        if ends_with_result(trimmed, &mut in_comment) {
+                            break;
+                        }
+                    }
+                }
             }
+        }
 
-            if possible_moves.len() == 1 {
-                movement.from_row = possible_moves[0].from.0 as i8;
-                movement.from_col = possible_moves[0].from.1 as i8;
-            } else {
-                // We still have multiple movements to chose from.
-                return Err(ChessMoveError::TooManyPossibleMoves);
+        if text.trim().is_empty() {
+            None
+        } else {
+            Some((text, start))
+        }
+    }
+}
+
+/// Whether a line holds a single tag pair, like `[Event "Casual game"]`
+fn is_tag_line(line: &str) -> bool {
+    let line = line.trim_end();
+    match PGNParser::parse(Rule::metadata_block, line) {
+        Ok(mut pairs) => pairs.next().map(|pair| pair.as_span().end()) == Some(line.len()),
+        Err(_) => false,
+    }
+}
+
+/// Whether a line of movetext has the result of the game outside its comments. `in_comment`
+/// tells whether the line starts inside a `{` comment, and is updated to where it ends.
+fn ends_with_result(line: &str, in_comment: &mut bool) -> bool {
+    let mut found = false;
+    let mut token = String::new();
+    let mut end_token = |token: &mut String| {
+        found |= ["1-0", "0-1", "1/2-1/2", "*"].contains(&token.as_str());
+        token.clear();
+    };
+    for c in line.chars() {
+        match c {
+            '}' if *in_comment => *in_comment = false,
+            _ if *in_comment => {}
+            '{' => {
+                end_token(&mut token);
+                *in_comment = true;
             }
+            // The rest of the line is a comment
+            ';' => break,
+            '(' | ')' => end_token(&mut token),
+            _ if c.is_whitespace() => end_token(&mut token),
+            _ => token.push(c),
         }
+    }
+    end_token(&mut token);
+    found
+}
 
-        // Do the move!
-        self.board.move_piece(Move {
-            from: (movement.from_row as usize, movement.from_col as usize),
-            to: (movement.to_row as usize, movement.to_col as usize),
-            castling: false,
-        })
+impl<R: Read> Iterator for PgnReader<R> {
+    type Item = Result<PgnRecord, PgnError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (source, start) = self.next_game_text()?;
+        let parsed = match PGNParser::parse(Rule::game, &source) {
+            Ok(mut pairs) => pairs.next().expect("A parsed game has a root pair"),
+            Err(e) => return Some(Err(PgnError::from(e).shifted(start - 1))),
+        };
+
+        let mut tags = Vec::new();
+        let mut movetext_start = None;
+        let mut movetext_end = 0;
+        for child_node in parsed.into_inner() {
+            match child_node.as_rule() {
+                Rule::metadata_block => tags.push(tag_pair(child_node)),
+                Rule::movetext | Rule::game_result => {
+                    let span = child_node.as_span();
+                    movetext_start.get_or_insert(span.start());
+                    movetext_end = span.end();
+                }
+                _ => {}
+            }
+        }
+        let movetext = source[movetext_start.unwrap_or(movetext_end)..movetext_end].to_string();
+        Some(Ok(PgnRecord {
+            tags,
+            movetext,
+            line: start,
+            source,
+        }))
     }
 }
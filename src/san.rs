@@ -0,0 +1,242 @@
+use thiserror::Error;
+
+use crate::PieceType::{Bishop, King, Knight, Pawn, Queen, Rook};
+use crate::{
+    pos_from_str, pos_to_str, ChessBoard, File2Index, Move, PieceType, Rank2Index, BOARD_SIZE,
+    KINGSIDE_KING_COL,
+};
+
+/// Why a SAN string couldn't be turned into a move, see [ChessBoard::parse_san]
+#[derive(Debug, PartialEq, Eq, Error)]
+pub enum SanError {
+    #[error("The SAN string is empty")]
+    EmptyString,
+    #[error("Invalid SAN syntax: {0}")]
+    InvalidSyntax(String),
+    #[error("No legal move matches {0}")]
+    IllegalMove(String),
+    #[error("The move {0} matches several legal moves")]
+    AmbiguousMove(String),
+}
+
+/// The SAN letter of a piece type, pawns have none
+fn piece_letter(piece_type: PieceType) -> &'static str {
+    match piece_type {
+        Pawn => "",
+        Knight => "N",
+        Bishop => "B",
+        Rook => "R",
+        Queen => "Q",
+        King => "K",
+    }
+}
+
+/// Writes a move of the side to move in Standard Algebraic Notation, e.g. `Nbd7`, `exd5`,
+/// `e8=Q+`, `O-O-O` or `Qh4#`.
+///
+/// The move is expected to be legal on `board`. Only the disambiguation needed to tell the move
+/// apart from the other legal moves is added: the file if it is enough, otherwise the rank, and
+/// both as a last resort.
+///
+/// ```
+/// use std::str::FromStr;
+/// use ajedrez::{to_san, FENStringParsing, Move, INITIAL_FEN_BOARD};
+///
+/// let board = INITIAL_FEN_BOARD.parse_fen().unwrap();
+/// assert_eq!("Nf3", to_san(&board, Move::from_str("g1f3").unwrap()));
+/// ```
+pub fn to_san(board: &ChessBoard, mov: Move) -> String {
+    let piece = match board.piece_at(mov.from) {
+        Some(piece) => piece,
+        None => return String::new(),
+    };
+
+    // A king moving two squares sideways castles, even if the move isn't flagged as such
+    let mov = Move {
+        castling: mov.castling || (piece.piece_type == King && mov.from.1.abs_diff(mov.to.1) == 2),
+        ..mov
+    };
+
+    let mut san = String::new();
+    if mov.castling {
+        san.push_str(if mov.to.1 == KINGSIDE_KING_COL {
+            "O-O"
+        } else {
+            "O-O-O"
+        });
+    } else {
+        let capture = board.piece_at(mov.to).is_some() || board.is_en_passant(mov);
+        san.push_str(piece_letter(piece.piece_type));
+        if piece.piece_type == Pawn {
+            if capture {
+                san.push_str(&pos_to_str(mov.from)[..1]);
+            }
+        } else {
+            san.push_str(&disambiguation(board, mov, piece.piece_type));
+        }
+        if capture {
+            san.push('x');
+        }
+        san.push_str(&pos_to_str(mov.to));
+        if let Some(promotion) = mov.promotion {
+            san.push('=');
+            san.push_str(piece_letter(promotion));
+        }
+    }
+
+    // Check and checkmate
+    let mut after = board.clone();
+    if after.make_move(mov).is_ok() && after.is_check() {
+        san.push(if after.legal_moves().is_empty() {
+            '#'
+        } else {
+            '+'
+        });
+    }
+    san
+}
+
+/// The part of the origin square needed to tell `mov` apart from the moves of the other pieces
+/// of the same type that land on the same square
+fn disambiguation(board: &ChessBoard, mov: Move, piece_type: PieceType) -> String {
+    let others: Vec<(usize, usize)> = board
+        .legal_moves()
+        .into_iter()
+        .filter(|m| {
+            m.to == mov.to
+                && m.from != mov.from
+                && !m.castling
+                && board.piece_at(m.from).map(|p| p.piece_type) == Some(piece_type)
+        })
+        .map(|m| m.from)
+        .collect();
+
+    let from = pos_to_str(mov.from);
+    if others.is_empty() {
+        String::new()
+    } else if others.iter().all(|o| o.1 != mov.from.1) {
+        from[..1].to_string()
+    } else if others.iter().all(|o| o.0 != mov.from.0) {
+        from[1..].to_string()
+    } else {
+        from
+    }
+}
+
+/// The piece type of a SAN piece letter
+fn piece_from_letter(letter: char) -> Option<PieceType> {
+    match letter {
+        'N' => Some(Knight),
+        'B' => Some(Bishop),
+        'R' => Some(Rook),
+        'Q' => Some(Queen),
+        'K' => Some(King),
+        _ => None,
+    }
+}
+
+impl ChessBoard {
+    /// Finds the legal move of the side to move written in Standard Algebraic Notation.
+    ///
+    /// Check and checkmate suffixes and annotations (`!`, `?`, `!?` ...) are ignored, castling
+    /// may be written with letters or zeros (`O-O`, `0-0-0`), and the `=` of promotions is
+    /// optional. The disambiguation only needs to tell apart the legal moves, so a pinned piece
+    /// never makes a move ambiguous.
+    ///
+    /// ```
+    /// use ajedrez::{FENStringParsing, SanError, INITIAL_FEN_BOARD};
+    ///
+    /// let board = INITIAL_FEN_BOARD.parse_fen().unwrap();
+    /// let mov = board.parse_san("Nf3!?").unwrap();
+    /// assert_eq!(((7, 6), (5, 5)), (mov.from, mov.to));
+    /// assert_eq!(Err(SanError::IllegalMove("Nd4".to_string())), board.parse_san("Nd4"));
+    /// ```
+    pub fn parse_san(&self, san: &str) -> Result<Move, SanError> {
+        let san = san.trim();
+        let text = san.trim_end_matches(['+', '#', '!', '?']);
+        if text.is_empty() {
+            return Err(SanError::EmptyString);
+        }
+        let invalid = || SanError::InvalidSyntax(san.to_string());
+
+        // Castling
+        let castling_col = match text {
+            "O-O" | "0-0" => Some(6),
+            "O-O-O" | "0-0-0" => Some(2),
+            _ => None,
+        };
+        if let Some(col) = castling_col {
+            return self
+                .legal_moves()
+                .into_iter()
+                .find(|m| m.castling && m.to.1 == col)
+                .ok_or_else(|| SanError::IllegalMove(san.to_string()));
+        }
+
+        let mut chars: Vec<char> = text.chars().collect();
+        let piece_type = match chars.first().and_then(|c| piece_from_letter(*c)) {
+            Some(piece_type) => {
+                chars.remove(0);
+                piece_type
+            }
+            None => Pawn,
+        };
+
+        // The promotion piece ends the move, with or without `=`
+        let mut promotion = None;
+        if let Some(p) = chars.last().and_then(|c| piece_from_letter(*c)) {
+            if piece_type != Pawn || p == King {
+                return Err(invalid());
+            }
+            promotion = Some(p);
+            chars.pop();
+            if chars.last() == Some(&'=') {
+                chars.pop();
+            }
+        }
+
+        // The target square comes last, the disambiguation and capture mark before it
+        if chars.len() < 2 {
+            return Err(invalid());
+        }
+        let target: String = chars.split_off(chars.len() - 2).into_iter().collect();
+        let to = pos_from_str(&target).map_err(|_| invalid())?;
+        if chars.last() == Some(&'x') {
+            chars.pop();
+        }
+        let (mut from_row, mut from_col) = (None, None);
+        for c in chars {
+            if let Ok(col) = c.file_to_zero_base_index() {
+                if from_col.is_some() {
+                    return Err(invalid());
+                }
+                from_col = Some(col);
+            } else if let Ok(rank) = c.rank_to_zero_base_index() {
+                if from_row.is_some() {
+                    return Err(invalid());
+                }
+                from_row = Some(BOARD_SIZE - 1 - rank);
+            } else {
+                return Err(invalid());
+            }
+        }
+
+        let candidates: Vec<Move> = self
+            .legal_moves()
+            .into_iter()
+            .filter(|m| {
+                !m.castling
+                    && m.to == to
+                    && m.promotion == promotion
+                    && self.piece_at(m.from).map(|p| p.piece_type) == Some(piece_type)
+                    && from_row.is_none_or(|row| m.from.0 == row)
+                    && from_col.is_none_or(|col| m.from.1 == col)
+            })
+            .collect();
+        match candidates.len() {
+            0 => Err(SanError::IllegalMove(san.to_string())),
+            1 => Ok(candidates[0]),
+            _ => Err(SanError::AmbiguousMove(san.to_string())),
+        }
+    }
+}
@@ -0,0 +1,301 @@
+//! A plain alpha-beta search with iterative deepening, enough for the library to play a game
+//! through the `uci` binary.
+//!
+//! ```
+//! use std::sync::atomic::AtomicBool;
+//! use ajedrez::{search, FENStringParsing, Score, SearchLimits};
+//!
+//! // White mates on the back rank
+//! let board = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1".parse_fen().unwrap();
+//! let limits = SearchLimits { depth: Some(2), time: None };
+//! let mut score = None;
+//! let best = search(&board, &limits, &AtomicBool::new(false), |info| score = Some(info.score));
+//! assert_eq!("a1a8", best.unwrap().to_string());
+//! assert_eq!(Some(Score::Mate(1)), score);
+//! ```
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::bitboard::squares;
+use crate::Color::White;
+use crate::PieceType::{Bishop, King, Knight, Pawn, Queen, Rook};
+use crate::{ChessBoard, Move, PieceType, BOARD_SIZE};
+
+/// The deepest search when no depth is given
+const MAX_DEPTH: u32 = 64;
+/// The score of a mate on the board. Mates further away score a bit less, so the quickest one
+/// is preferred.
+const MATE: i32 = 100_000;
+const INFINITY: i32 = 1_000_000;
+/// How many nodes are searched between checks of the clock and the stop flag
+const NODES_BETWEEN_CHECKS: u64 = 1024;
+
+/// When to stop searching. Without limits the search goes on until it is stopped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchLimits {
+    /// The number of half moves to look ahead
+    pub depth: Option<u32>,
+    /// The time to think
+    pub time: Option<Duration>,
+}
+
+/// The value of a position for the side to move
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Score {
+    /// The material and position balance, in hundredths of a pawn
+    Centipawns(i32),
+    /// Mate in so many moves, negative when the side to move gets mated
+    Mate(i32),
+}
+
+/// What a search found after completing one depth, see [search]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchInfo {
+    pub depth: u32,
+    pub score: Score,
+    /// The positions visited so far
+    pub nodes: u64,
+    /// The time spent so far
+    pub time: Duration,
+    /// The principal variation: the best line for both sides, starting with the best move
+    pub pv: Vec<Move>,
+}
+
+/// The value of a piece, in centipawns
+fn piece_value(piece_type: PieceType) -> i32 {
+    match piece_type {
+        Pawn => 100,
+        Knight => 320,
+        Bishop => 330,
+        Rook => 500,
+        Queen => 900,
+        King => 0,
+    }
+}
+
+/// A bonus for pieces near the center and for pawns that went forward
+fn square_bonus(piece_type: PieceType, row: usize, col: usize, advance: usize) -> i32 {
+    // 0 on the four central squares, 6 in the corners
+    let center_distance = (2 * row).abs_diff(7) / 2 + (2 * col).abs_diff(7) / 2;
+    match piece_type {
+        Pawn => 5 * advance as i32,
+        Knight | Bishop => 10 - 4 * center_distance as i32,
+        Queen => 5 - center_distance as i32,
+        Rook | King => 0,
+    }
+}
+
+/// The static evaluation of the position, in centipawns, for the side to move
+pub fn evaluate(board: &ChessBoard) -> i32 {
+    let mut score = 0;
+    for (row, col) in squares(board.occupied()) {
+        let piece = board
+            .piece_at((row, col))
+            .expect("The bitboards match the pieces");
+        let advance = match piece.color {
+            White => BOARD_SIZE - 1 - row,
+            _ => row,
+        };
+        let value = piece_value(piece.piece_type)
+            + square_bonus(piece.piece_type, row, col, advance.saturating_sub(1));
+        score += if piece.color == board.active_color {
+            value
+        } else {
+            -value
+        };
+    }
+    score
+}
+
+/// Searches the best move of the position until a limit is reached or `stop` is set, telling
+/// `report` about every completed depth. Returns `None` when there are no legal moves.
+pub fn search(
+    board: &ChessBoard,
+    limits: &SearchLimits,
+    stop: &AtomicBool,
+    mut report: impl FnMut(&SearchInfo),
+) -> Option<Move> {
+    let mut searcher = Searcher {
+        stop,
+        start: Instant::now(),
+        time: limits.time,
+        nodes: 0,
+        aborted: false,
+    };
+    let mut board = board.clone();
+    let mut moves = board.legal_moves();
+    let mut best = *moves.first()?;
+
+    for depth in 1..=limits.depth.unwrap_or(MAX_DEPTH) {
+        // The best move of the previous depth is searched first
+        order_moves(&board, &mut moves);
+        if let Some(index) = moves.iter().position(|&m| m == best) {
+            moves[..=index].rotate_right(1);
+        }
+
+        let mut alpha = -INFINITY;
+        let mut pv = Vec::new();
+        for &mov in &moves {
+            let undo = board.play(mov);
+            let mut line = Vec::new();
+            let score = -searcher.negamax(&mut board, depth - 1, 1, -INFINITY, -alpha, &mut line);
+            board.unmake_move(undo);
+            if searcher.aborted {
+                break;
+            }
+            if score > alpha {
+                alpha = score;
+                pv = [vec![mov], line].concat();
+            }
+        }
+        // A depth cut short is only trusted for a move that beats the previous best one
+        if let Some(&mov) = pv.first() {
+            best = mov;
+        }
+        if searcher.aborted {
+            break;
+        }
+
+        let score = if alpha.abs() >= MATE - MAX_DEPTH as i32 * 2 {
+            let plies = MATE - alpha.abs();
+            Score::Mate(alpha.signum() * (plies + 1) / 2)
+        } else {
+            Score::Centipawns(alpha)
+        };
+        report(&SearchInfo {
+            depth,
+            score,
+            nodes: searcher.nodes,
+            time: searcher.start.elapsed(),
+            pv,
+        });
+
+        // A mate can't get any closer, and the next depth would take longer than what is left
+        let out_of_time = limits
+            .time
+            .is_some_and(|time| searcher.start.elapsed() * 2 > time);
+        if matches!(score, Score::Mate(_)) || out_of_time {
+            break;
+        }
+    }
+    Some(best)
+}
+
+/// Puts promotions and captures first, the most valuable victims before the others
+fn order_moves(board: &ChessBoard, moves: &mut [Move]) {
+    moves.sort_by_key(|&mov| {
+        let victim = board
+            .piece_at(mov.to)
+            .filter(|_| !mov.castling)
+            .map_or(0, |piece| piece_value(piece.piece_type));
+        let promotion = mov.promotion.map_or(0, piece_value);
+        -(victim + promotion)
+    });
+}
+
+struct Searcher<'a> {
+    stop: &'a AtomicBool,
+    start: Instant,
+    time: Option<Duration>,
+    nodes: u64,
+    /// Whether the search ran out of time or was stopped, the scores being meaningless then
+    aborted: bool,
+}
+
+impl Searcher<'_> {
+    fn check_limits(&mut self) {
+        self.nodes += 1;
+        if self.nodes.is_multiple_of(NODES_BETWEEN_CHECKS)
+            && (self.stop.load(Ordering::Relaxed)
+                || self.time.is_some_and(|time| self.start.elapsed() >= time))
+        {
+            self.aborted = true;
+        }
+    }
+
+    /// The score of the position for the side to move, `ply` half moves from the root. `pv`
+    /// gets the best line found.
+    fn negamax(
+        &mut self,
+        board: &mut ChessBoard,
+        depth: u32,
+        ply: i32,
+        mut alpha: i32,
+        beta: i32,
+        pv: &mut Vec<Move>,
+    ) -> i32 {
+        self.check_limits();
+        if self.aborted {
+            return 0;
+        }
+        let mut moves = board.legal_moves();
+        if moves.is_empty() {
+            return if board.is_check() { ply - MATE } else { 0 };
+        }
+        if board.half_moves >= 100 {
+            return 0;
+        }
+        if depth == 0 {
+            return self.quiescence(board, alpha, beta);
+        }
+
+        order_moves(board, &mut moves);
+        for mov in moves {
+            let undo = board.play(mov);
+            let mut line = Vec::new();
+            let score = -self.negamax(board, depth - 1, ply + 1, -beta, -alpha, &mut line);
+            board.unmake_move(undo);
+            if self.aborted {
+                return 0;
+            }
+            if score > alpha {
+                alpha = score;
+                *pv = [vec![mov], line].concat();
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+        alpha
+    }
+
+    /// Plays the captures and promotions until the position is quiet, so the static evaluation
+    /// isn't fooled by a piece about to be taken
+    fn quiescence(&mut self, board: &mut ChessBoard, mut alpha: i32, beta: i32) -> i32 {
+        self.check_limits();
+        if self.aborted {
+            return 0;
+        }
+        // Standing pat: the side to move doesn't have to capture
+        let stand_pat = evaluate(board);
+        if stand_pat >= beta {
+            return stand_pat;
+        }
+        alpha = alpha.max(stand_pat);
+
+        let mut moves: Vec<Move> = board
+            .legal_moves()
+            .into_iter()
+            .filter(|&mov| {
+                mov.promotion.is_some()
+                    || board.is_en_passant(mov)
+                    || (!mov.castling && board.piece_at(mov.to).is_some())
+            })
+            .collect();
+        order_moves(board, &mut moves);
+        for mov in moves {
+            let undo = board.play(mov);
+            let score = -self.quiescence(board, -beta, -alpha);
+            board.unmake_move(undo);
+            if self.aborted {
+                return 0;
+            }
+            if score >= beta {
+                return score;
+            }
+            alpha = alpha.max(score);
+        }
+        alpha
+    }
+}
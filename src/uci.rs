@@ -0,0 +1,108 @@
+//! Moves in the long algebraic notation of the Universal Chess Interface: the square the piece
+//! leaves and the one it lands on, e.g. `e2e4`, followed by the promotion piece, e.g. `e7e8q`.
+//! The null move is written `0000`.
+//!
+//! [Move]'s `FromStr` and `Display` read and write the notation as is. Reading it through a
+//! [ChessBoard] also tells which moves castle or promote.
+//!
+//! Castling is written as a king move, `e1g1`, except in Chess960 where the king takes its own
+//! rook, e.g. `e1h1`: there the king may castle to a square it could also step to, or not move at
+//! all.
+
+use std::str::FromStr;
+
+use crate::ChessMove::{CastleKingside, CastleQueenside};
+use crate::Color::{Black, White};
+use crate::PieceType::{King, Pawn, Rook};
+use crate::{
+    ChessBoard, Move, ParseError, Piece, BOARD_SIZE, KINGSIDE_KING_COL, QUEENSIDE_KING_COL,
+};
+
+impl ChessBoard {
+    /// The move in UCI notation, castling being written as Chess960 wants when
+    /// [ChessBoard::chess960] is set
+    ///
+    /// ```
+    /// use ajedrez::{ChessBoard, FENStringParsing};
+    ///
+    /// let mut board = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1".parse_fen().unwrap();
+    /// let castling = board.parse_uci("e1g1").unwrap();
+    /// assert!(castling.castling);
+    /// board.chess960 = true;
+    /// assert_eq!("e1h1", board.to_uci(castling));
+    /// ```
+    pub fn to_uci(&self, mov: Move) -> String {
+        if mov.castling && self.chess960 {
+            let castle_type = if mov.to.1 == KINGSIDE_KING_COL {
+                CastleKingside
+            } else {
+                CastleQueenside
+            };
+            let rook_col = self
+                .piece_at(mov.from)
+                .and_then(|king| self.castling_rights.rook_col(king.color, castle_type));
+            if let Some(rook_col) = rook_col {
+                return Move::new(mov.from, (mov.from.0, rook_col)).to_string();
+            }
+        }
+        mov.to_string()
+    }
+
+    /// Reads a move in UCI notation, telling castling apart from other king moves: a king taking
+    /// a rook of its own it may castle with in Chess960, and a king moving two squares sideways
+    /// in standard chess. Pawns reaching the last rank must name their promotion piece, and no
+    /// other move may. The move isn't checked any further.
+    ///
+    /// ```
+    /// use ajedrez::{FENStringParsing, ParseError, PieceType};
+    ///
+    /// let board = "4k3/P7/8/8/8/8/8/4K3 w - - 0 1".parse_fen().unwrap();
+    /// assert_eq!(Some(PieceType::Knight), board.parse_uci("a7a8n").unwrap().promotion);
+    /// assert_eq!(Err(ParseError::MissingPromotionPiece), board.parse_uci("a7a8"));
+    /// assert_eq!(Err(ParseError::InvalidPromotionPiece), board.parse_uci("e1e2q"));
+    /// ```
+    pub fn parse_uci(&self, uci: &str) -> Result<Move, ParseError> {
+        let mov = Move::from_str(uci)?;
+        let piece = match self.piece_at(mov.from) {
+            Some(piece) if !mov.is_null() => piece,
+            _ => return Ok(mov),
+        };
+
+        let last_row = match piece.color {
+            White => 0,
+            Black => BOARD_SIZE - 1,
+        };
+        let promotes = piece.piece_type == Pawn && mov.to.0 == last_row;
+        match mov.promotion {
+            None if promotes => return Err(ParseError::MissingPromotionPiece),
+            Some(_) if !promotes => return Err(ParseError::InvalidPromotionPiece),
+            _ => {}
+        }
+
+        // Castling is written as a move of the king along its rank
+        if piece.piece_type != King || mov.from.0 != mov.to.0 {
+            return Ok(mov);
+        }
+        if self.piece_at(mov.to) == Some(Piece::new(piece.color, Rook)) {
+            for (castle_type, king_col) in [
+                (CastleKingside, KINGSIDE_KING_COL),
+                (CastleQueenside, QUEENSIDE_KING_COL),
+            ] {
+                if self.castling_rights.rook_col(piece.color, castle_type) == Some(mov.to.1) {
+                    return Ok(Move {
+                        to: (mov.to.0, king_col),
+                        castling: true,
+                        ..mov
+                    });
+                }
+            }
+        }
+        if !self.chess960 && mov.from.1.abs_diff(mov.to.1) == 2 {
+            return Ok(Move {
+                castling: true,
+                ..mov
+            });
+        }
+        Ok(mov)
+    }
+}
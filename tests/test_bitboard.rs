@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod tests {
+    use ajedrez::bitboard::{
+        bishop_attacks, king_attacks, knight_attacks, pawn_attacks, queen_attacks, rook_attacks,
+        square_bit, square_index, square_position, squares, Bitboard,
+    };
+    use ajedrez::{pos_from_str, Color, FENStringParsing, PieceType, INITIAL_FEN_BOARD};
+
+    fn index(s: &str) -> usize {
+        square_index(pos_from_str(s).unwrap())
+    }
+
+    fn bitboard(positions: &[&str]) -> Bitboard {
+        positions
+            .iter()
+            .map(|s| square_bit(pos_from_str(s).unwrap()))
+            .fold(0, |b, bit| b | bit)
+    }
+
+    #[test]
+    fn test_square_index() {
+        assert_eq!(0, index("a8"));
+        assert_eq!(63, index("h1"));
+        assert_eq!(pos_from_str("e4").unwrap(), square_position(index("e4")));
+        assert_eq!(
+            vec![pos_from_str("a8").unwrap(), pos_from_str("h1").unwrap()],
+            squares(bitboard(&["h1", "a8"])).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_leaper_attacks() {
+        assert_eq!(bitboard(&["b3", "c2"]), knight_attacks(index("a1")));
+        assert_eq!(8, knight_attacks(index("e4")).count_ones());
+        assert_eq!(bitboard(&["g1", "g2", "h2"]), king_attacks(index("h1")));
+        assert_eq!(
+            bitboard(&["d5", "f5"]),
+            pawn_attacks(Color::White, index("e4"))
+        );
+        assert_eq!(bitboard(&["b3"]), pawn_attacks(Color::Black, index("a4")));
+    }
+
+    #[test]
+    fn test_slider_attacks() {
+        // Empty board
+        assert_eq!(14, rook_attacks(index("d4"), 0).count_ones());
+        assert_eq!(13, bishop_attacks(index("d4"), 0).count_ones());
+        assert_eq!(27, queen_attacks(index("d4"), 0).count_ones());
+
+        // The blockers are attacked, the squares behind them are not
+        let occupied = bitboard(&["d6", "b4", "f2"]);
+        let attacks = rook_attacks(index("d4"), occupied);
+        assert_ne!(0, attacks & bitboard(&["d6", "b4", "d1", "h4"]));
+        assert_eq!(0, attacks & bitboard(&["d7", "a4"]));
+        let attacks = bishop_attacks(index("d4"), occupied);
+        assert_eq!(bitboard(&["f2"]), attacks & bitboard(&["f2", "g1"]));
+    }
+
+    #[test]
+    fn test_board_bitboards() {
+        let board = INITIAL_FEN_BOARD.parse_fen().unwrap();
+        assert_eq!(32, board.occupied().count_ones());
+        assert_eq!(16, board.color_bitboard(Color::Black).count_ones());
+        assert_eq!(
+            bitboard(&["b1", "g1"]),
+            board.bitboard(PieceType::Knight, Color::White)
+        );
+        assert_eq!(2, board.find_pieces(PieceType::Rook, Color::Black).len());
+
+        // f3 is defended by the pawns at e2 and g2, and the knight at g1
+        let attackers = board.attackers(pos_from_str("f3").unwrap(), Color::White);
+        assert_eq!(bitboard(&["e2", "g2", "g1"]), attackers);
+        assert!(!board.is_square_attacked(pos_from_str("e4").unwrap(), Color::Black));
+    }
+}
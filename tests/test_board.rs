@@ -3,8 +3,8 @@ mod tests {
     use ajedrez::Color::{Black, White};
     use ajedrez::PieceType::{King, Rook};
     use ajedrez::{
-        ChessBoard, Color, Piece, PieceType, Square, DEFAULT_KINGSIDE_ROOK_COL, DEFAULT_KING_COL,
-        DEFAULT_QUEENSIDE_ROOK_COL,
+        CastlingRights, ChessBoard, ChessMove, Color, Piece, PieceType, Square,
+        DEFAULT_KINGSIDE_ROOK_COL, DEFAULT_KING_COL, DEFAULT_QUEENSIDE_ROOK_COL,
     };
 
     #[test]
@@ -32,6 +32,7 @@ mod tests {
     #[test]
     fn test_castling() {
         let mut board = ChessBoard::new();
+        board.castling_rights = CastlingRights::ALL;
         let castling = board
             .set_piece_0(
                 0,
@@ -89,16 +90,17 @@ mod tests {
             )
             .get_castling(true);
 
-        assert_eq!(castling.white_kingside, true);
-        assert_eq!(castling.white_queenside, true);
-        assert_eq!(castling.black_kingside, true);
-        assert_eq!(castling.black_queenside, true);
-        assert_eq!(castling.check_empty_rows, true);
+        assert!(castling.white_kingside);
+        assert!(castling.white_queenside);
+        assert!(castling.black_kingside);
+        assert!(castling.black_queenside);
+        assert!(castling.check_empty_rows);
     }
 
     #[test]
     fn test_castling_white() {
         let mut board = ChessBoard::new();
+        board.castling_rights = CastlingRights::ALL;
         let castling = board
             .set_piece_0(
                 7,
@@ -129,15 +131,16 @@ mod tests {
             )
             .get_castling(true);
 
-        assert_eq!(castling.white_kingside, true);
-        assert_eq!(castling.white_queenside, true);
-        assert_eq!(castling.black_kingside, false);
-        assert_eq!(castling.black_queenside, false);
+        assert!(castling.white_kingside);
+        assert!(castling.white_queenside);
+        assert!(!castling.black_kingside);
+        assert!(!castling.black_queenside);
     }
 
     #[test]
     fn test_castling_white_piece_moved() {
         let mut board = ChessBoard::new();
+        board.castling_rights = CastlingRights::ALL;
         let mut castling = board
             .set_piece_0(
                 7,
@@ -168,8 +171,8 @@ mod tests {
             )
             .get_castling(true);
 
-        assert_eq!(castling.white_kingside, false);
-        assert_eq!(castling.white_queenside, false);
+        assert!(!castling.white_kingside);
+        assert!(!castling.white_queenside);
 
         // The king hasn't moved
         castling = board
@@ -183,8 +186,8 @@ mod tests {
                 }),
             )
             .get_castling(true);
-        assert_eq!(castling.white_kingside, false);
-        assert_eq!(castling.white_queenside, false);
+        assert!(!castling.white_kingside);
+        assert!(!castling.white_queenside);
 
         // Kingside Rook didn't move
         castling = board
@@ -198,8 +201,8 @@ mod tests {
                 }),
             )
             .get_castling(true);
-        assert_eq!(castling.white_kingside, true);
-        assert_eq!(castling.white_queenside, false);
+        assert!(castling.white_kingside);
+        assert!(!castling.white_queenside);
 
         // Qeeenside Rook didn't move
         castling = board
@@ -213,8 +216,8 @@ mod tests {
                 }),
             )
             .get_castling(true);
-        assert_eq!(castling.white_kingside, true);
-        assert_eq!(castling.white_queenside, true);
+        assert!(castling.white_kingside);
+        assert!(castling.white_queenside);
 
         // The king moved, but the rooks didn't
         castling = board
@@ -228,13 +231,14 @@ mod tests {
                 }),
             )
             .get_castling(true);
-        assert_eq!(castling.white_kingside, false);
-        assert_eq!(castling.white_queenside, false);
+        assert!(!castling.white_kingside);
+        assert!(!castling.white_queenside);
     }
 
     #[test]
     fn test_castling_black() {
         let mut board = ChessBoard::new();
+        board.castling_rights = CastlingRights::ALL;
         let castling = board
             .set_piece_0(
                 0,
@@ -265,10 +269,32 @@ mod tests {
             )
             .get_castling(true);
 
-        assert_eq!(castling.white_kingside, false);
-        assert_eq!(castling.white_queenside, false);
-        assert_eq!(castling.black_kingside, true);
-        assert_eq!(castling.black_queenside, true);
-        assert_eq!(castling.check_empty_rows, true);
+        assert!(!castling.white_kingside);
+        assert!(!castling.white_queenside);
+        assert!(castling.black_kingside);
+        assert!(castling.black_queenside);
+        assert!(castling.check_empty_rows);
+    }
+
+    #[test]
+    fn test_castling_rights() {
+        // The pieces are on their home squares, but the rights are gone
+        let mut board = ChessBoard::new();
+        board
+            .set_piece_0(7, DEFAULT_QUEENSIDE_ROOK_COL, Some(Piece::new(White, Rook)))
+            .set_piece_0(7, DEFAULT_KING_COL, Some(Piece::new(White, King)))
+            .set_piece_0(7, DEFAULT_KINGSIDE_ROOK_COL, Some(Piece::new(White, Rook)));
+        assert!(!board.get_castling(true).white_kingside);
+        assert!(!board.get_castling(true).white_queenside);
+
+        board.castling_rights.white_kingside = Some(DEFAULT_KINGSIDE_ROOK_COL);
+        let castling = board.get_castling(true);
+        assert!(castling.white_kingside);
+        assert!(!castling.white_queenside);
+        assert_eq!("K", board.get_castling_as_string());
+
+        assert_eq!("HAha", CastlingRights::ALL.to_string());
+        assert_eq!("-", CastlingRights::NONE.to_string());
+        assert!(CastlingRights::ALL.has(Black, ChessMove::CastleQueenside));
     }
 }
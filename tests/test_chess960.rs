@@ -0,0 +1,143 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use ajedrez::{
+        to_san, BoardAsFEN, ChessBoard, ChessMove, Color, FENStringParsing, Move, PgnLine,
+        PieceType, Square, INITIAL_FEN_BOARD,
+    };
+
+    /// White has its king on b1 and rooks on a1 and g1, Black its king on g8 and rooks on b8 and
+    /// h8. Both may castle to both sides.
+    const CASTLING_FEN: &str = "1r4kr/pppppppp/8/8/8/8/PPPPPPPP/RK4R1 w KQkq - 0 1";
+
+    fn chess960_board(fen: &str) -> ChessBoard {
+        fen.parse_fen_strict_960().unwrap()
+    }
+
+    fn castling_move(board: &ChessBoard, king_to: usize) -> Move {
+        board
+            .legal_moves()
+            .into_iter()
+            .find(|m| m.castling && m.to.1 == king_to)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_starting_positions() {
+        let standard = ChessBoard::new_chess960(518).unwrap();
+        assert_eq!(INITIAL_FEN_BOARD, standard.as_fen());
+        assert_eq!(
+            "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1",
+            ChessBoard::new_chess960(0).unwrap().as_fen()
+        );
+        assert_eq!(
+            "rkrnnqbb/pppppppp/8/8/8/8/PPPPPPPP/RKRNNQBB w KQkq - 0 1",
+            ChessBoard::new_chess960(959).unwrap().as_fen()
+        );
+        assert!(ChessBoard::new_chess960(960).is_none());
+
+        let mut positions = HashSet::new();
+        for index in 0..960 {
+            let board = ChessBoard::new_chess960(index).unwrap();
+            assert!(board.chess960);
+            assert_eq!(Ok(()), board.validate(), "{index}");
+            let bishops = board.find_pieces(PieceType::Bishop, Color::White);
+            let dark = |square: &Square| (square.row + square.col) % 2;
+            assert_ne!(dark(&bishops[0]), dark(&bishops[1]), "{index}");
+            positions.insert(board.as_fen());
+        }
+        assert_eq!(960, positions.len());
+    }
+
+    #[test]
+    fn test_castling() {
+        let mut board = chess960_board(CASTLING_FEN);
+        assert_eq!(Some(6), board.castling_rights.white_kingside);
+        assert_eq!(Some(1), board.castling_rights.black_queenside);
+
+        // The king crosses the whole rank to g1 and the rook lands on f1
+        let kingside = castling_move(&board, 6);
+        assert_eq!(((7, 1), (7, 6)), (kingside.from, kingside.to));
+        assert_eq!("O-O", to_san(&board, kingside));
+        let undo = board.make_move(kingside).unwrap();
+        assert_eq!(
+            "1r4kr/pppppppp/8/8/8/8/PPPPPPPP/R4RK1 b kq - 1 1",
+            board.as_fen()
+        );
+        board.unmake_move(undo);
+        assert_eq!(CASTLING_FEN, board.as_fen());
+
+        // Queenside the king steps to c1, like a plain king move
+        let queenside = castling_move(&board, 2);
+        assert_eq!(queenside, board.parse_san("O-O-O").unwrap());
+        assert!(board.legal_moves().contains(&Move::new((7, 1), (7, 2))));
+        let line = PgnLine::from_moves(&board, &[queenside]);
+        assert_eq!("O-O-O", line.unwrap().moves[0].san);
+        let line = PgnLine::from_moves(&board, &[Move::new((7, 1), (7, 2))]);
+        assert_eq!("Kc1", line.unwrap().moves[0].san);
+        board
+            .castle(Color::White, ChessMove::CastleQueenside)
+            .unwrap();
+        assert_eq!(
+            "1r4kr/pppppppp/8/8/8/8/PPPPPPPP/2KR2R1 b kq - 1 1",
+            board.as_fen()
+        );
+
+        // The black king doesn't move when castling kingside
+        let castling = castling_move(&board, 6);
+        assert_eq!(castling.from, castling.to);
+        board.make_move(castling).unwrap();
+        assert_eq!(
+            "1r3rk1/pppppppp/8/8/8/8/PPPPPPPP/2KR2R1 w - - 2 2",
+            board.as_fen()
+        );
+        assert_eq!(1, board.find_pieces(PieceType::King, Color::Black).len());
+    }
+
+    #[test]
+    fn test_castling_rules() {
+        // Standard chess only castles from the e file
+        let mut board = CASTLING_FEN.parse_fen().unwrap();
+        assert!(board.validate().is_err());
+        assert!(!board.can_castle(Color::White, ChessMove::CastleQueenside, true));
+        board.chess960 = true;
+        assert!(board.can_castle(Color::White, ChessMove::CastleQueenside, true));
+
+        // A knight on d1 is in the way of the rook
+        let board = chess960_board("1r4kr/pppppppp/8/8/8/8/PPPPPPPP/RK1N2R1 w KQkq - 0 1");
+        assert!(!board.can_castle(Color::White, ChessMove::CastleQueenside, true));
+        // A rook on e8 attacks a square the king crosses
+        let board = chess960_board("1r2r1k1/ppp3pp/8/8/8/8/PPP3PP/RK4R1 w KQ - 0 1");
+        assert!(!board.can_castle(Color::White, ChessMove::CastleKingside, true));
+        assert!(board.can_castle(Color::White, ChessMove::CastleQueenside, true));
+        // The rook on b1 hides the attack of the rook on a1 on c1, where the king lands
+        let board = chess960_board("7k/8/8/8/8/8/8/rR1K4 w B - 0 1");
+        assert!(!board.can_castle(Color::White, ChessMove::CastleQueenside, true));
+        assert!(!board.legal_moves().iter().any(|m| m.castling));
+    }
+
+    #[test]
+    fn test_uci_castling() {
+        let board = chess960_board(CASTLING_FEN);
+        // The king takes its own rook
+        let queenside = castling_move(&board, 2);
+        assert_eq!("b1a1", board.to_uci(queenside));
+        assert_eq!(queenside, board.parse_uci("b1a1").unwrap());
+        assert_eq!("b1g1", board.to_uci(castling_move(&board, 6)));
+        assert_eq!(castling_move(&board, 6), board.parse_uci("b1g1").unwrap());
+        // A plain king move
+        assert_eq!(Move::new((7, 1), (7, 2)), board.parse_uci("b1c1").unwrap());
+        assert_eq!("b1c1", board.to_uci(Move::new((7, 1), (7, 2))));
+
+        // Standard chess writes castling as the king's move
+        let mut board = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1".parse_fen().unwrap();
+        let kingside = board.parse_uci("e1g1").unwrap();
+        assert_eq!(castling_move(&board, 6), kingside);
+        assert_eq!("e1g1", board.to_uci(kingside));
+        board.chess960 = true;
+        assert_eq!("e1h1", board.to_uci(kingside));
+        assert_eq!(kingside, board.parse_uci("e1h1").unwrap());
+        assert!(!board.parse_uci("e1g1").unwrap().castling);
+    }
+}
@@ -0,0 +1,172 @@
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::str::FromStr;
+
+    use ajedrez::{
+        to_san, BoardAsFEN, Epd, EpdError, EpdOperand, FENStringParsing, Move, ParseError,
+        SanError, INITIAL_FEN_BOARD,
+    };
+
+    #[test]
+    fn test_test_suite() {
+        let input = fs::read_to_string("tests/epd/wac.epd").expect("Can't open EPD file");
+        let suite: Vec<Epd> = input.lines().map(|line| line.parse().unwrap()).collect();
+        assert_eq!(3, suite.len());
+
+        let ids: Vec<_> = suite.iter().map(|epd| epd.id().unwrap()).collect();
+        assert_eq!(vec!["WAC.001", "WAC.002", "WAC.003"], ids);
+        let best: Vec<_> = suite
+            .iter()
+            .map(|epd| to_san(&epd.board, epd.best_moves()[0]))
+            .collect();
+        assert_eq!(vec!["Qg6", "Rxb2", "Rg3"], best);
+        assert!(suite[1].avoid_moves().is_empty());
+
+        // Without counters the position starts the game
+        assert_eq!(
+            "8/7p/5k2/5p2/p1p2P2/Pr1pPK2/1P1R3P/8 b - - 0 1",
+            suite[1].board.as_fen()
+        );
+        // Written back as read
+        for (epd, line) in suite.iter().zip(input.lines()) {
+            assert_eq!(line, epd.to_epd());
+        }
+    }
+
+    #[test]
+    fn test_operands() {
+        let line = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - acd 12; am f3 g4; \
+            c0 \"Lots of ways\"; ce -15; draw_offer; fmvn 1; hmvc 0; pv e4 e5 Nf3; \
+            sts 1 2 x;";
+        let epd = Epd::from_str(line).unwrap();
+        assert_eq!(INITIAL_FEN_BOARD, epd.board.as_fen());
+        let operand = |opcode: &str| epd.operations.get(opcode).unwrap().clone();
+        assert_eq!(EpdOperand::Integer(12), operand("acd"));
+        assert_eq!(EpdOperand::Integer(-15), operand("ce"));
+        assert_eq!(EpdOperand::Text("Lots of ways".to_string()), operand("c0"));
+        assert_eq!(EpdOperand::None, operand("draw_offer"));
+        assert_eq!(
+            EpdOperand::Tokens(vec!["1".to_string(), "2".to_string(), "x".to_string()]),
+            operand("sts")
+        );
+        assert_eq!(
+            vec![
+                Move::from_str("f2f3").unwrap(),
+                Move::from_str("g2g4").unwrap()
+            ],
+            epd.avoid_moves()
+        );
+        // The moves of the variation follow on from each other
+        assert_eq!(
+            EpdOperand::Moves(vec![
+                Move::from_str("e2e4").unwrap(),
+                Move::from_str("e7e5").unwrap(),
+                Move::from_str("g1f3").unwrap(),
+            ]),
+            operand("pv")
+        );
+        assert_eq!(line.replace("\\", ""), epd.to_string());
+
+        // The move counters come from hmvc and fmvn
+        let epd: Epd = "8/8/8/4k3/8/8/8/4K3 b - - hmvc 7; fmvn 42;"
+            .parse()
+            .unwrap();
+        assert_eq!("8/8/8/4k3/8/8/8/4K3 b - - 7 42", epd.board.as_fen());
+    }
+
+    #[test]
+    fn test_errors() {
+        let cases = [
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq",
+                EpdError::Syntax {
+                    col: 51,
+                    message: "expected position_field".to_string(),
+                },
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPP/RNBQKBNR w KQkq - bm e4;",
+                EpdError::InvalidPosition(ParseError::TooFewSquaresInRank { rank: 2 }),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - bm e4; bm d4;",
+                EpdError::DuplicateOpcode("bm".to_string()),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - acd deep;",
+                EpdError::InvalidOperand {
+                    opcode: "acd".to_string(),
+                    operand: "deep".to_string(),
+                },
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - bm e5;",
+                EpdError::InvalidMove {
+                    opcode: "bm".to_string(),
+                    san: "e5".to_string(),
+                    source: SanError::IllegalMove("e5".to_string()),
+                },
+            ),
+        ];
+        for (line, error) in cases {
+            assert_eq!(Some(error), Epd::from_str(line).err(), "{line}");
+        }
+    }
+
+    #[test]
+    fn test_new() {
+        let mut epd = Epd::new(INITIAL_FEN_BOARD.parse_fen().unwrap());
+        assert_eq!(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -",
+            epd.to_epd()
+        );
+        epd.operations.insert(
+            "bm".to_string(),
+            EpdOperand::Moves(vec![Move::from_str("e2e4").unwrap()]),
+        );
+        epd.operations
+            .insert("id".to_string(), EpdOperand::Text("start".to_string()));
+        assert_eq!(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - bm e4; id \"start\";",
+            epd.to_epd()
+        );
+    }
+
+    #[test]
+    fn test_to_epd_illegal_moves() {
+        let mut epd = Epd::new(INITIAL_FEN_BOARD.parse_fen().unwrap());
+        let moves = |moves: &[&str]| {
+            EpdOperand::Moves(moves.iter().map(|m| Move::from_str(m).unwrap()).collect())
+        };
+        // The variation is written up to its first illegal move
+        epd.operations
+            .insert("pv".to_string(), moves(&["e2e4", "e2e4", "g1f3"]));
+        epd.operations
+            .insert("bm".to_string(), moves(&["d2d4", "d2d5"]));
+        // Without any legal move the opcode is left out
+        epd.operations.insert("am".to_string(), moves(&["e1e2"]));
+        assert_eq!(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - bm d4; pv e4;",
+            epd.to_epd()
+        );
+        assert!(Epd::from_str(&epd.to_epd()).is_ok());
+    }
+
+    #[test]
+    fn test_text_round_trip() {
+        let mut epd = Epd::new(INITIAL_FEN_BOARD.parse_fen().unwrap());
+        let text = r#"He said "1. e4; best by test" \o/"#;
+        epd.operations
+            .insert("c0".to_string(), EpdOperand::Text(text.to_string()));
+        assert_eq!(
+            r#"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - c0 "He said \"1. e4; best by test\" \\o/";"#,
+            epd.to_epd()
+        );
+        let read = Epd::from_str(&epd.to_epd()).unwrap();
+        assert_eq!(
+            Some(&EpdOperand::Text(text.to_string())),
+            read.operations.get("c0")
+        );
+    }
+}
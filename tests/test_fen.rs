@@ -1,6 +1,10 @@
 #[cfg(test)]
 mod tests {
-    use ajedrez::{BoardAsFEN, ChessBoard, Color, FENStringParsing, PieceType, INITIAL_FEN_BOARD};
+    use ajedrez::{
+        BoardAsFEN, CastlingRights, ChessBoard, ChessMove, Color, FENStringParsing, Move,
+        ParseError, PieceType, Square, INITIAL_FEN_BOARD,
+    };
+    use std::str::FromStr;
 
     #[test]
     fn test_board_parse_fen() {
@@ -87,13 +91,14 @@ mod tests {
         // Current turn/ active color
         assert_eq!(Color::White, board.active_color);
 
-        // TODO: Castling
+        // Castling
+        assert_eq!(CastlingRights::ALL, board.castling_rights);
         // En passant
         assert_eq!(None, board.passant_square);
         // Half moves
         assert_eq!(0, board.half_moves);
         // Full moves
-        assert_eq!(0, board.full_moves);
+        assert_eq!(1, board.full_moves);
     }
 
     #[test]
@@ -118,4 +123,217 @@ mod tests {
         let fen = "8/8/2rbk3/3P4/8/8/8/8 w - - 0 0";
         assert_eq!(fen, fen.parse_fen().unwrap().as_fen());
     }
+
+    #[test]
+    fn test_board_as_fen_en_passant() {
+        let fen = "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2";
+        assert_eq!(fen, fen.parse_fen().unwrap().as_fen());
+
+        let mut board = INITIAL_FEN_BOARD.parse_fen().unwrap();
+        board.move_piece(Move::from_str("e2e4").unwrap()).unwrap();
+        assert!(board.as_fen().contains(" b KQkq e3 "));
+    }
+
+    #[test]
+    fn test_board_as_fen_castling() {
+        // The castling field is taken as is, even with the pieces on their home squares
+        for fen in [
+            "r3k2r/8/8/8/8/8/8/R3K2R w - - 0 1",
+            "r3k2r/8/8/8/8/8/8/R3K2R w Kq - 0 1",
+            "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1",
+        ] {
+            assert_eq!(fen, fen.parse_fen().unwrap().as_fen());
+        }
+
+        let mut board = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1".parse_fen().unwrap();
+        // Moving a rook loses the right on its side ...
+        board.move_piece(Move::from_str("h1h2").unwrap()).unwrap();
+        assert!(board.as_fen().contains(" b Qkq - "));
+        // ... capturing one takes it from the opponent ...
+        board.move_piece(Move::from_str("a8a1").unwrap()).unwrap();
+        assert!(board.as_fen().contains(" w k - "));
+        // ... and moving the king loses both
+        board.move_piece(Move::from_str("h2h3").unwrap()).unwrap();
+        board.move_piece(Move::from_str("e8f8").unwrap()).unwrap();
+        assert!(board.as_fen().contains(" w - - "));
+    }
+
+    #[test]
+    fn test_shredder_fen() {
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w HAha - 0 1";
+        let board = fen.parse_fen().unwrap();
+        assert_eq!(CastlingRights::ALL, board.castling_rights);
+        assert_eq!("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", board.as_fen());
+        assert_eq!(fen, board.as_shredder_fen());
+        assert_eq!("HAha", board.castling_rights.to_string());
+    }
+
+    #[test]
+    fn test_x_fen() {
+        // White may castle with the rooks on g1 and b1, Black with the ones on h8 and b8. Only
+        // the rooks that aren't the outermost on their side need their file.
+        let fen = "rr2k2r/8/8/8/8/8/8/1R2K1RR w GQkb - 0 1";
+        let mut board = fen.parse_fen_strict().unwrap();
+        let rights = CastlingRights {
+            white_kingside: Some(6),
+            white_queenside: Some(1),
+            black_kingside: Some(7),
+            black_queenside: Some(1),
+        };
+        assert_eq!(rights, board.castling_rights);
+        assert_eq!(fen, board.as_fen());
+        assert_eq!(
+            "rr2k2r/8/8/8/8/8/8/1R2K1RR w GBhb - 0 1",
+            board.as_shredder_fen()
+        );
+        assert_eq!(
+            rights,
+            board.as_shredder_fen().parse_fen().unwrap().castling_rights
+        );
+        // Without the position, the rights can only be written in Shredder-FEN
+        assert_eq!("GBhb", rights.to_string());
+
+        // The king lands on g1 where its rook stood, and the rook on f1
+        assert!(board.can_castle(Color::White, ChessMove::CastleKingside, true));
+        assert!(board.can_castle(Color::White, ChessMove::CastleQueenside, true));
+        let castling = board
+            .legal_moves()
+            .into_iter()
+            .find(|m| m.castling && m.to == (7, 6))
+            .unwrap();
+        let undo = board.make_move(castling).unwrap();
+        assert_eq!("rr2k2r/8/8/8/8/8/8/1R3RKR b kb - 1 1", board.as_fen());
+        board.unmake_move(undo);
+        assert_eq!(fen, board.as_fen());
+
+        board
+            .castle(Color::White, ChessMove::CastleQueenside)
+            .unwrap();
+        assert_eq!("rr2k2r/8/8/8/8/8/8/2KR2RR b kb - 1 1", board.as_fen());
+
+        // A piece on the landing square of the rook blocks castling
+        let board = "r3k2r/8/8/8/8/8/8/RN2K1RR w GA - 0 1".parse_fen().unwrap();
+        assert!(!board.can_castle(Color::White, ChessMove::CastleQueenside, true));
+        assert!(board.can_castle(Color::White, ChessMove::CastleKingside, true));
+    }
+
+    #[test]
+    fn test_parse_fen_errors() {
+        let cases = [
+            (
+                "rnbqkbnr/ppppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                ParseError::TooManySquaresInRank { rank: 7 },
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/44/8/PPPPPPPP/RNBQKBN w KQkq - 0 1",
+                ParseError::TooFewSquaresInRank { rank: 1 },
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e4 0 1",
+                ParseError::InvalidEnPassant,
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 99999999999",
+                ParseError::InvalidMoveCounter,
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq",
+                ParseError::InvalidFENString,
+            ),
+            // The same right twice
+            (
+                "r3k2r/8/8/8/8/8/8/R3K2R w KHkq - 0 1",
+                ParseError::InvalidCastlingRights,
+            ),
+            // A rook can't share the file of the king
+            (
+                "r3k2r/8/8/8/8/8/8/R3K2R w Ekq - 0 1",
+                ParseError::InvalidCastlingRights,
+            ),
+        ];
+        for (fen, error) in cases {
+            assert_eq!(Err(error), fen.parse_fen().map(|_| ()), "{fen}");
+        }
+
+        // Lenient parsing keeps positions without kings
+        assert!("8/8/8/8/8/8/8/8 w - - 0 1".parse_fen().is_ok());
+    }
+
+    #[test]
+    fn test_validate_position() {
+        let cases = [
+            (
+                "8/8/8/8/8/8/8/4K3 w - - 0 1",
+                ParseError::MissingKing(Color::Black),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/8 w - - 0 1",
+                ParseError::MissingKing(Color::White),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/3KK3 w - - 0 1",
+                ParseError::TooManyKings(Color::White),
+            ),
+            ("4k3/8/8/8/8/8/8/P3K3 w - - 0 1", ParseError::PawnOnBackRank),
+            ("4k2p/8/8/8/8/8/8/4K3 b - - 0 1", ParseError::PawnOnBackRank),
+            // No black pawn on d5
+            (
+                "4k3/8/8/8/8/8/8/4K3 w - d6 0 1",
+                ParseError::InvalidEnPassant,
+            ),
+            // Without a rook on h1 White can't castle kingside
+            (
+                "r3k2r/8/8/8/8/8/8/R3K3 w KQkq - 0 1",
+                ParseError::InvalidCastlingRights,
+            ),
+            (
+                "r3k2r/8/8/8/8/8/8/R4K1R w Kkq - 0 1",
+                ParseError::InvalidCastlingRights,
+            ),
+            // Black is in check with White to move
+            (
+                "4k3/8/8/8/8/8/8/4RK2 w - - 0 1",
+                ParseError::OpponentInCheck,
+            ),
+        ];
+        for (fen, error) in cases {
+            assert!(fen.parse_fen().is_ok(), "{fen}");
+            assert_eq!(Err(error), fen.parse_fen_strict().map(|_| ()), "{fen}");
+        }
+
+        for fen in [
+            INITIAL_FEN_BOARD,
+            "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            // The side to move may be in check
+            "4k3/8/8/8/8/8/8/4RK2 b - - 0 1",
+        ] {
+            assert!(fen.parse_fen_strict().is_ok(), "{fen}");
+        }
+
+        // The castling rights of a king off the e-file are only valid in Chess960
+        let fen = "1r4kr/pppppppp/8/8/8/8/PPPPPPPP/RK4R1 w KQkq - 0 1";
+        assert_eq!(
+            Err(ParseError::InvalidCastlingRights),
+            fen.parse_fen_strict().map(|_| ())
+        );
+        assert!(fen.parse_fen_strict_960().unwrap().chess960);
+        assert!(INITIAL_FEN_BOARD.parse_fen_strict_960().is_ok());
+    }
+
+    #[test]
+    fn test_validate_passant_square_off_its_rank() {
+        // Squares the FEN parser doesn't let through, set by hand
+        for (rank, row) in [(8, 0), (1, 7), (3, 5)] {
+            let mut board = "4k3/8/8/8/8/8/8/4K3 w - - 0 1".parse_fen().unwrap();
+            board.passant_square = Some(Square {
+                piece: None,
+                rank,
+                file: 'e',
+                row,
+                col: 4,
+            });
+            assert_eq!(Err(ParseError::InvalidEnPassant), board.validate());
+        }
+    }
 }
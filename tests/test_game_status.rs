@@ -0,0 +1,119 @@
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use ajedrez::{
+        pos_from_str, Color, FENStringParsing, GameStatus, Move, PositionKey, INITIAL_FEN_BOARD,
+    };
+
+    #[test]
+    fn test_find_king() {
+        let board = INITIAL_FEN_BOARD.parse_fen().unwrap();
+        assert_eq!(
+            Some(pos_from_str("e1").unwrap()),
+            board.find_king(Color::White)
+        );
+        assert_eq!(
+            Some(pos_from_str("e8").unwrap()),
+            board.find_king(Color::Black)
+        );
+
+        let board = "8/8/8/8/8/8/8/4K3 w - - 0 1".parse_fen().unwrap();
+        assert_eq!(None, board.find_king(Color::Black));
+    }
+
+    #[test]
+    fn test_ongoing() {
+        let board = INITIAL_FEN_BOARD.parse_fen().unwrap();
+        assert_eq!(GameStatus::Ongoing, board.game_status(&[]));
+        assert!(!board.game_status(&[]).is_over());
+    }
+
+    #[test]
+    fn test_checkmate() {
+        // Fool's mate
+        let board = "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3"
+            .parse_fen()
+            .unwrap();
+        assert!(board.is_check());
+        assert!(board.is_checkmate());
+        let status = board.game_status(&[]);
+        assert_eq!(GameStatus::Checkmate(Color::Black), status);
+        assert!(status.is_over());
+        assert!(!status.is_draw());
+    }
+
+    #[test]
+    fn test_stalemate() {
+        let board = "7k/5Q2/6K1/8/8/8/8/8 b - - 0 1".parse_fen().unwrap();
+        assert!(!board.is_check());
+        assert!(board.is_stalemate());
+        assert_eq!(GameStatus::Stalemate, board.game_status(&[]));
+        assert!(board.game_status(&[]).is_draw());
+    }
+
+    #[test]
+    fn test_move_rules() {
+        let mut board = "4k3/8/8/8/8/8/4P3/R3K3 w - - 99 80".parse_fen().unwrap();
+        assert_eq!(GameStatus::Ongoing, board.game_status(&[]));
+
+        // A quiet move reaches the fifty-move rule ...
+        board.move_piece(Move::from_str("a1a2").unwrap()).unwrap();
+        assert_eq!(100, board.half_moves);
+        assert_eq!(GameStatus::FiftyMoveRule, board.game_status(&[]));
+
+        // ... while a pawn move resets the counter
+        board.move_piece(Move::from_str("e8d8").unwrap()).unwrap();
+        board.move_piece(Move::from_str("e2e4").unwrap()).unwrap();
+        assert_eq!(0, board.half_moves);
+        assert_eq!(GameStatus::Ongoing, board.game_status(&[]));
+
+        let board = "4k3/8/8/8/8/8/4P3/R3K3 w - - 150 100".parse_fen().unwrap();
+        assert_eq!(GameStatus::SeventyFiveMoveRule, board.game_status(&[]));
+    }
+
+    #[test]
+    fn test_repetitions() {
+        let mut board = INITIAL_FEN_BOARD.parse_fen().unwrap();
+        let mut history: Vec<PositionKey> = Vec::new();
+        let shuffle = ["g1f3", "g8f6", "f3g1", "f6g8"];
+
+        for (ix, mov) in shuffle.iter().cycle().take(16).enumerate() {
+            history.push(board.position_key());
+            board.move_piece(Move::from_str(mov).unwrap()).unwrap();
+            let status = board.game_status(&history);
+            match ix {
+                // The initial position is back for the third time after 8 half-moves ...
+                7..=14 => assert_eq!(GameStatus::ThreefoldRepetition, status),
+                // ... and for the fifth time after 16.
+                15 => assert_eq!(GameStatus::FivefoldRepetition, status),
+                _ => assert_eq!(GameStatus::Ongoing, status),
+            }
+        }
+    }
+
+    #[test]
+    fn test_insufficient_material() {
+        for fen in [
+            "8/8/4k3/8/8/3K4/8/8 w - - 0 1",
+            "8/8/4k3/8/8/3KN3/8/8 w - - 0 1",
+            "8/8/4k3/8/8/3KB3/8/8 w - - 0 1",
+            // Both bishops are on dark squares
+            "8/8/4k3/2b5/8/3KB3/8/8 w - - 0 1",
+        ] {
+            let board = fen.parse_fen().unwrap();
+            assert!(board.is_insufficient_material(), "{fen}");
+            assert_eq!(GameStatus::InsufficientMaterial, board.game_status(&[]));
+        }
+
+        for fen in [
+            "8/8/4k3/8/8/3KP3/8/8 w - - 0 1",
+            "8/8/4k3/8/8/2NKN3/8/8 w - - 0 1",
+            // Bishops on squares of different colors
+            "8/8/4k3/3b4/8/3KB3/8/8 w - - 0 1",
+        ] {
+            let board = fen.parse_fen().unwrap();
+            assert!(!board.is_insufficient_material(), "{fen}");
+        }
+    }
+}
@@ -0,0 +1,148 @@
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::str::FromStr;
+
+    use ajedrez::{
+        write_pgn, BoardAsFEN, ChessMoveError, FENStringParsing, GameTree, Move, PGNGame,
+        INITIAL_FEN_BOARD,
+    };
+
+    fn annotated_tree() -> GameTree {
+        let input =
+            fs::read_to_string("tests/pgn_games/annotated.pgn").expect("Can't open PGN file");
+        PGNGame::new(&input).unwrap().tree().unwrap()
+    }
+
+    #[test]
+    fn test_tree_from_pgn() {
+        let input =
+            fs::read_to_string("tests/pgn_games/annotated.pgn").expect("Can't open PGN file");
+        let game = PGNGame::new(&input).unwrap();
+        let tree = game.tree().unwrap();
+        assert_eq!(vec!["The Italian Game"], tree.root().comments);
+        assert_eq!(15, tree.mainline().len());
+        assert_eq!(
+            write_pgn(&[], game.movetext(), "*"),
+            write_pgn(&[], &tree.to_pgn_line(), "*")
+        );
+
+        // Illegal moves in a variation are found too
+        let broken = "1. e4 e5 (1... Ke5) 2. Nf3 *";
+        assert!(PGNGame::new(broken).unwrap().tree().is_err());
+    }
+
+    #[test]
+    fn test_cursor_navigation() {
+        let mut tree = annotated_tree();
+        let mut cursor = tree.cursor();
+        assert!(!cursor.back());
+        assert!(cursor.goto_ply(5));
+        assert_eq!("Bc4", cursor.node().san());
+        assert_eq!(
+            "r1bqkbnr/pppp1ppp/2n5/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R b KQkq - 3 3",
+            cursor.board().as_fen()
+        );
+        assert!(cursor.goto_ply(2));
+        assert_eq!(
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2",
+            cursor.board().as_fen()
+        );
+        assert!(cursor.forward());
+        assert_eq!("Nf3", cursor.node().san());
+
+        // The main line ends at 8. O-O
+        assert!(!cursor.goto_ply(20));
+        assert_eq!(15, cursor.ply());
+        assert_eq!("O-O", cursor.node().san());
+        assert!(!cursor.forward());
+        assert!(cursor.goto_ply(0));
+        assert_eq!(INITIAL_FEN_BOARD, cursor.board().as_fen());
+    }
+
+    #[test]
+    fn test_cursor_variations() {
+        let mut tree = annotated_tree();
+        let mut cursor = tree.cursor();
+        cursor.goto_ply(5);
+        assert_eq!(1, cursor.variations().len());
+        assert!(!cursor.enter_variation(1));
+        assert!(cursor.enter_variation(0));
+        assert_eq!("Nf6", cursor.node().san());
+        assert_eq!(vec!["Two Knights"], cursor.node().comments);
+
+        // Into the nested variation 4... Bc5!?
+        cursor.forward();
+        assert!(cursor.enter_variation(0));
+        assert_eq!("Bc5", cursor.node().san());
+        assert!(cursor.forward());
+        assert_eq!("Bxf7+", cursor.node().san());
+        assert!(!cursor.forward());
+
+        // 4... Bc5 becomes the main continuation of the Two Knights
+        assert!(cursor.promote_variation());
+        assert_eq!(9, cursor.ply());
+        cursor.goto_ply(7);
+        assert_eq!("d5", cursor.variations()[0].san());
+        cursor.forward();
+        assert_eq!("Bc5", cursor.node().san());
+
+        // Then the Two Knights becomes the main line
+        assert!(cursor.promote_variation());
+        assert!(!cursor.promote_variation());
+        cursor.goto_ply(0);
+        cursor.goto_ply(8);
+        assert_eq!("Bc5", cursor.node().san());
+
+        // Deleting the old main line leaves a single line at move 3
+        cursor.goto_ply(5);
+        assert!(cursor.enter_variation(0));
+        assert_eq!("Bc5", cursor.node().san());
+        assert!(cursor.delete_variation());
+        assert_eq!(5, cursor.ply());
+        assert!(cursor.variations().is_empty());
+        assert!(!cursor.delete_variation());
+        drop(cursor);
+
+        let line = tree.to_pgn_line();
+        let sans: Vec<&str> = line.moves.iter().map(|m| m.san.as_str()).collect();
+        assert_eq!(
+            vec!["e4", "e5", "Nf3", "Nc6", "Bc4", "Nf6", "Ng5", "Bc5", "Bxf7+"],
+            sans
+        );
+        assert_eq!(3, line.moves[7].variations[0].moves.len());
+    }
+
+    #[test]
+    fn test_cursor_add_move() {
+        let mut tree = GameTree::new(INITIAL_FEN_BOARD.parse_fen().unwrap());
+        let mut cursor = tree.cursor();
+        let mov = |m: &str| Move::from_str(m).unwrap();
+        for m in ["e2e4", "e7e5", "g1f3"] {
+            cursor.add_move(mov(m)).unwrap();
+        }
+        cursor.goto_ply(1);
+        // An existing move is followed, a new one starts a variation
+        cursor.add_move(mov("e7e5")).unwrap();
+        assert!(cursor.variations().is_empty());
+        cursor.back();
+        cursor.add_move(mov("c7c5")).unwrap();
+        assert_eq!("c5", cursor.node().san());
+        cursor.node_mut().nags.push(1);
+        assert_eq!(
+            Err(ChessMoveError::IllegalMove),
+            cursor.add_move(mov("e1e3"))
+        );
+        cursor.add_move(mov("g1f3")).unwrap();
+        drop(cursor);
+
+        assert_eq!(3, tree.mainline().len());
+        assert_eq!(
+            "1. e4 e5 (1... c5 $1 2. Nf3) 2. Nf3 *\n",
+            write_pgn(&[], &tree.to_pgn_line(), "*")
+                .split("\n\n")
+                .nth(1)
+                .unwrap()
+        );
+    }
+}
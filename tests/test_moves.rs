@@ -3,7 +3,8 @@ mod tests {
     use std::str::FromStr;
 
     use ajedrez::{
-        pos_from_str, ChessMove, Color, FENStringParsing, Move, ParseError, BOARD_SIZE_RANGE_0,
+        pos_from_str, BoardAsFEN, ChessMove, ChessMoveError, Color, FENStringParsing, Move,
+        ParseError, PieceType, BOARD_SIZE_RANGE_0, PROMOTION_PIECES,
     };
 
     #[test]
@@ -24,6 +25,26 @@ mod tests {
         mov = Move::from_str("a1a8").expect("parsing move should not have failed");
         assert_eq!((7, 0), mov.from);
         assert_eq!((0, 0), mov.to);
+
+        // Promotions and the null move
+        mov = Move::from_str("e7e8Q").unwrap();
+        assert_eq!(Some(PieceType::Queen), mov.promotion);
+        assert_eq!(
+            Err(ParseError::InvalidPromotionPiece),
+            Move::from_str("e7e8k")
+        );
+        assert!(Move::from_str("0000").unwrap().is_null());
+    }
+
+    #[test]
+    fn test_move_display() {
+        for uci in ["e2e4", "a1h8", "e7e8q", "b2a1n", "0000"] {
+            assert_eq!(uci, Move::from_str(uci).unwrap().to_string());
+        }
+        assert_eq!(
+            "g7g8r",
+            Move::new_promotion((1, 6), (0, 6), PieceType::Rook).to_string()
+        );
     }
 
     #[test]
@@ -351,7 +372,7 @@ mod tests {
     #[test]
     fn test_castling_kingside() {
         // Example board taken from wikipedia, Castling
-        let board = "r3k2r/8/8/8/8/8/8/R3K2R w - - 0 0".parse_fen().unwrap();
+        let board = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 0".parse_fen().unwrap();
         assert!(board.can_castle(Color::White, ChessMove::CastleKingside, true));
         assert!(board.can_castle(Color::Black, ChessMove::CastleKingside, true));
     }
@@ -359,7 +380,7 @@ mod tests {
     #[test]
     fn test_castling_queenside() {
         // Example board taken from wikipedia, Castling
-        let board = "r3k2r/8/8/8/8/8/8/R3K2R w - - 0 0".parse_fen().unwrap();
+        let board = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 0".parse_fen().unwrap();
         assert!(board.can_castle(Color::Black, ChessMove::CastleQueenside, true));
         assert!(board.can_castle(Color::White, ChessMove::CastleQueenside, true));
     }
@@ -451,4 +472,231 @@ mod tests {
         assert_eq!((4, 2), possible_moves[6].to);
         assert_eq!((4, 4), possible_moves[7].to);
     }
+
+    #[test]
+    fn test_legal_moves_initial() {
+        let board = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+            .parse_fen()
+            .unwrap();
+        assert_eq!(20, board.legal_moves().len());
+    }
+
+    #[test]
+    fn test_legal_moves_pinned_piece() {
+        // The knight at e2 is pinned by the rook at e8, so only the king can move
+        let board = "4r2k/8/8/8/8/8/4N3/4K3 w - - 0 1".parse_fen().unwrap();
+        let moves = board.legal_moves();
+        assert_eq!(4, moves.len());
+        assert!(moves.iter().all(|m| m.from == pos_from_str("e1").unwrap()));
+    }
+
+    #[test]
+    fn test_legal_moves_check_evasion() {
+        // The white king is in check by the rook at a1: it may step off the first rank, the
+        // bishop may capture the rook or block the check at c1
+        let board = "7k/8/8/8/8/8/1B6/r3K3 w - - 0 1".parse_fen().unwrap();
+        let moves = board.legal_moves();
+        assert_eq!(5, moves.len());
+        for mov in ["b2a1", "b2c1", "e1d2", "e1e2", "e1f2"] {
+            assert!(moves.contains(&Move::from_str(mov).unwrap()));
+        }
+        // The rook still targets f1 through the square left by the king
+        assert!(!moves.contains(&Move::from_str("e1f1").unwrap()));
+    }
+
+    #[test]
+    fn test_legal_moves_castling() {
+        let board = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1".parse_fen().unwrap();
+        let castling: Vec<Move> = board
+            .legal_moves()
+            .into_iter()
+            .filter(|m| m.castling)
+            .collect();
+        assert_eq!(2, castling.len());
+
+        // The bishop at a6 attacks f1, so white cannot castle kingside
+        let board = "r3k2r/8/b7/8/8/8/8/R3K2R w KQkq - 0 1".parse_fen().unwrap();
+        let castling: Vec<Move> = board
+            .legal_moves()
+            .into_iter()
+            .filter(|m| m.castling)
+            .collect();
+        assert_eq!(1, castling.len());
+        assert_eq!(pos_from_str("c1").unwrap(), castling[0].to);
+    }
+
+    #[test]
+    fn test_legal_moves_checkmate() {
+        let board = "5r2/7q/6N1/8/1P1k4/5Q2/B7/3R2K1 b - - 0 0"
+            .parse_fen()
+            .unwrap();
+        assert!(board.legal_moves().is_empty());
+    }
+
+    #[test]
+    fn test_en_passant_moves() {
+        // Black just played d7d5, the white pawn at e5 may capture it en passant
+        let mut board = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1".parse_fen().unwrap();
+        let possible_moves = board.generate_intrinsic_pawn_moves(pos_from_str("e5").unwrap());
+        assert_eq!(2, possible_moves.len());
+        assert_eq!(pos_from_str("d6").unwrap(), possible_moves[1].to);
+        assert!(board.is_en_passant(possible_moves[1]));
+
+        // The captured pawn is removed from the board
+        board.move_piece(Move::from_str("e5d6").unwrap()).unwrap();
+        assert!(board.get_piece_a("d5").is_none());
+        assert_eq!('P', board.get_piece_a("d6").unwrap().as_fen());
+        assert_eq!(None, board.passant_square);
+    }
+
+    #[test]
+    fn test_en_passant_square_is_set() {
+        let mut board = "4k3/3p4/8/4P3/8/8/8/4K3 b - - 0 1".parse_fen().unwrap();
+        board.move_piece(Move::from_str("d7d5").unwrap()).unwrap();
+        let passant = board.passant_square.unwrap();
+        assert_eq!(pos_from_str("d6").unwrap(), (passant.row, passant.col));
+        assert!(board
+            .legal_moves()
+            .contains(&Move::from_str("e5d6").unwrap()));
+
+        // The en passant target only lasts for one move
+        board.move_piece(Move::from_str("e1e2").unwrap()).unwrap();
+        assert_eq!(None, board.passant_square);
+    }
+
+    #[test]
+    fn test_en_passant_discovered_check() {
+        // Capturing en passant would remove both pawns from the 5th rank and expose the king
+        let board = "8/8/8/K2pP2r/8/8/8/7k w - d6 0 1".parse_fen().unwrap();
+        assert!(!board
+            .legal_moves()
+            .contains(&Move::from_str("e5d6").unwrap()));
+    }
+
+    #[test]
+    fn test_move_from_str_promotion() {
+        let mov = Move::from_str("e7e8q").expect("parsing move should not have failed");
+        assert_eq!((1, 4), mov.from);
+        assert_eq!((0, 4), mov.to);
+        assert_eq!(Some(PieceType::Queen), mov.promotion);
+        assert_eq!(
+            Some(PieceType::Knight),
+            Move::from_str("b2a1n").unwrap().promotion
+        );
+        assert_eq!(None, Move::from_str("e2e4").unwrap().promotion);
+        assert_eq!(
+            Err(ParseError::InvalidPromotionPiece),
+            Move::from_str("e7e8k")
+        );
+    }
+
+    #[test]
+    fn test_generate_pawn_moves_promotion() {
+        let board = "3r3k/4P3/8/8/8/8/8/4K3 w - - 0 1".parse_fen().unwrap();
+        let possible_moves = board.generate_intrinsic_pawn_moves(pos_from_str("e7").unwrap());
+        // Pushing to e8 and capturing on d8, both with 4 possible promotions
+        assert_eq!(8, possible_moves.len());
+        for (mov, piece_type) in possible_moves[..4].iter().zip(PROMOTION_PIECES) {
+            assert_eq!(pos_from_str("e8").unwrap(), mov.to);
+            assert_eq!(Some(piece_type), mov.promotion);
+        }
+        for (mov, piece_type) in possible_moves[4..].iter().zip(PROMOTION_PIECES) {
+            assert_eq!(pos_from_str("d8").unwrap(), mov.to);
+            assert_eq!(Some(piece_type), mov.promotion);
+        }
+    }
+
+    #[test]
+    fn test_move_piece_promotion() {
+        let mut board = "7k/8/8/8/8/8/p7/4K3 b - - 0 1".parse_fen().unwrap();
+
+        // The promotion piece is mandatory ...
+        assert_eq!(
+            Err(ChessMoveError::InvalidPromotion),
+            board.move_piece(Move::from_str("a2a1").unwrap())
+        );
+        // ... and only pawns on the last rank can be promoted
+        assert_eq!(
+            Err(ChessMoveError::InvalidPromotion),
+            board.move_piece(Move::from_str("h8g8q").unwrap())
+        );
+
+        board.move_piece(Move::from_str("a2a1n").unwrap()).unwrap();
+        assert_eq!('n', board.get_piece_a("a1").unwrap().as_fen());
+        assert!(board.get_piece_a("a2").is_none());
+    }
+
+    #[test]
+    fn test_make_move_castling_promotion() {
+        let mut board = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1".parse_fen().unwrap();
+        let castling = board
+            .legal_moves()
+            .into_iter()
+            .find(|m| m.castling && m.to == pos_from_str("g1").unwrap())
+            .unwrap();
+        let promoted = Move {
+            promotion: Some(PieceType::Queen),
+            ..castling
+        };
+        assert_eq!(
+            Err(ChessMoveError::InvalidPromotion),
+            board.make_move(promoted)
+        );
+        // The king is still there
+        assert_eq!('K', board.get_piece_a("e1").unwrap().as_fen());
+        assert!(board.make_move(castling).is_ok());
+    }
+
+    #[test]
+    fn test_make_unmake_move() {
+        // Castling, en passant, promotions and captures are all available
+        let mut board = "r3k2r/pPppqpb1/bn2pnp1/2pPN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq c6 0 1"
+            .parse_fen()
+            .unwrap();
+        let fen = board.as_fen();
+        let counters = |board: &ajedrez::ChessBoard| {
+            BOARD_SIZE_RANGE_0
+                .flat_map(|i| BOARD_SIZE_RANGE_0.map(move |j| (i, j)))
+                .map(|(i, j)| board.get_piece_0(i, j).map(|p| p.moves))
+                .collect::<Vec<_>>()
+        };
+        let initial_counters = counters(&board);
+
+        for mov in board.legal_moves() {
+            let undo = board.make_move(mov).unwrap();
+            assert_ne!(fen, board.as_fen());
+            board.unmake_move(undo);
+            assert_eq!(fen, board.as_fen(), "{:?}", mov);
+            assert_eq!(initial_counters, counters(&board), "{:?}", mov);
+        }
+    }
+
+    #[test]
+    fn test_make_unmake_castling() {
+        let mut board = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1".parse_fen().unwrap();
+        let castling = board
+            .legal_moves()
+            .into_iter()
+            .find(|m| m.castling && m.to == pos_from_str("c1").unwrap())
+            .unwrap();
+
+        let undo = board.make_move(castling).unwrap();
+        assert!(board.get_piece_a("c1").unwrap().piece_type == PieceType::King);
+        assert!(board.get_piece_a("d1").unwrap().piece_type == PieceType::Rook);
+        assert!(board.get_piece_a("a1").is_none());
+        assert_eq!(Color::Black, board.active_color);
+
+        // The king and rook are back, and they can castle again
+        board.unmake_move(undo);
+        assert!(board.get_piece_a("e1").unwrap().piece_type == PieceType::King);
+        assert!(board.get_piece_a("a1").unwrap().piece_type == PieceType::Rook);
+        assert!(board.can_castle(Color::White, ChessMove::CastleQueenside, true));
+        assert_eq!(Color::White, board.active_color);
+
+        // make_move() validates the moves like move_piece() does
+        assert_eq!(
+            Err(ChessMoveError::WrongPieceColor),
+            board.make_move(Move::from_str("e8g8").unwrap())
+        );
+    }
 }
@@ -0,0 +1,63 @@
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use ajedrez::{BoardAsFEN, ChessBoard, Epd, EpdOperand, FENStringParsing, INITIAL_FEN_BOARD};
+
+    /// Deeper counts take too long on debug builds
+    const MAX_DEPTH: u32 = 4;
+
+    /// Reads the positions of an EPD file with the expected perft counts as `;D<depth> <nodes>`
+    /// operations
+    fn load_epd(path: &str) -> Vec<(ChessBoard, Vec<(u32, u64)>)> {
+        let input = fs::read_to_string(path).expect("Can't open EPD file");
+        input
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let epd: Epd = line.parse().unwrap();
+                let counts = epd
+                    .operations
+                    .iter()
+                    .map(|(opcode, operand)| match operand {
+                        EpdOperand::Integer(nodes) => (
+                            opcode.trim_start_matches('D').parse().unwrap(),
+                            *nodes as u64,
+                        ),
+                        _ => panic!("{opcode} should be a node count"),
+                    })
+                    .collect();
+                (epd.board, counts)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_perft_suite() {
+        let positions = load_epd("tests/epd/perft.epd");
+        assert_eq!(7, positions.len());
+        for (board, counts) in positions {
+            for (depth, nodes) in counts {
+                if depth <= MAX_DEPTH {
+                    let fen = board.as_fen();
+                    assert_eq!(nodes, board.perft(depth), "{fen} at depth {depth}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_perft_divide() {
+        let board = INITIAL_FEN_BOARD.parse_fen().unwrap();
+        assert_eq!(1, board.perft(0));
+        assert!(board.perft_divide(0).is_empty());
+
+        let divide = board.perft_divide(2);
+        assert_eq!(20, divide.len());
+        assert!(divide.iter().all(|(_, nodes)| *nodes == 20));
+        assert_eq!(
+            board.perft(3),
+            board.perft_divide(3).iter().map(|(_, n)| n).sum::<u64>()
+        );
+    }
+}
@@ -1,6 +1,11 @@
 #[cfg(test)]
 mod tests {
-    use ajedrez::PGNGame;
+    use ajedrez::{
+        write_pgn, BoardAsFEN, ChessMoveError, Color, FENStringParsing, Move, PGNGame, ParseError,
+        PgnError, PgnLine, PgnReader, Piece, PieceType, PlyEvent, PrettyPrinter, ReplayObserver,
+        INITIAL_FEN_BOARD,
+    };
+    use std::str::FromStr;
     use std::{fs, io};
 
     #[test]
@@ -8,7 +13,7 @@ mod tests {
         let input = fs::read_to_string("tests/pgn_games/game1.pgn")
             .expect("Can't open anastasian-lewis.pgn");
         let game = PGNGame::new(&input);
-        game.unwrap().play();
+        game.unwrap().play().unwrap();
         Ok(())
     }
 
@@ -17,7 +22,7 @@ mod tests {
         let input = fs::read_to_string("tests/pgn_games/morphy_karl_isouard_1858.pgn")
             .expect("Can't open PGN file");
         let game = PGNGame::new(&input);
-        game.unwrap().play();
+        game.unwrap().play().unwrap();
         Ok(())
     }
 
@@ -26,7 +31,7 @@ mod tests {
         let input = fs::read_to_string("tests/pgn_games/byrne_fischer_1956.pgn")
             .expect("Can't open PGN file");
         let game = PGNGame::new(&input);
-        game.unwrap().play();
+        game.unwrap().play().unwrap();
         Ok(())
     }
 
@@ -35,7 +40,7 @@ mod tests {
         let input =
             fs::read_to_string("tests/pgn_games/fischer-spassky.pgn").expect("Can't open PGN file");
         let game = PGNGame::new(&input);
-        game.unwrap().play();
+        game.unwrap().play().unwrap();
         Ok(())
     }
 
@@ -44,7 +49,427 @@ mod tests {
         let input =
             fs::read_to_string("tests/pgn_games/shenzlong24.pgn").expect("Can't open PGN file");
         let game = PGNGame::new(&input);
-        game.unwrap().play();
+        game.unwrap().play().unwrap();
         Ok(())
     }
+
+    #[test]
+    fn test_promotion() -> io::Result<()> {
+        // 3.exd6 captures en passant and 5.exf8=Q+ promotes
+        let input =
+            fs::read_to_string("tests/pgn_games/promotion.pgn").expect("Can't open PGN file");
+        let game = PGNGame::new(&input);
+        game.unwrap().play().unwrap();
+        Ok(())
+    }
+
+    #[test]
+    fn test_syntax_error_location() {
+        let input = "[Event \"Broken\"]\n\n1. e4 e5 2. Nf3 Zc6 1-0";
+        match PGNGame::new(input) {
+            Err(PgnError::Syntax { line, col, .. }) => assert_eq!((3, 17), (line, col)),
+            other => panic!("Expected a syntax error, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn test_illegal_move_error() {
+        // No black knight can reach d5
+        let input = "[Event \"Illegal\"]\n\n1. e4 e5 2. Nf3 Nd5 1-0";
+        let error = PGNGame::new(input).unwrap().play().unwrap_err();
+        assert_eq!(
+            PgnError::InvalidMove {
+                line: 3,
+                col: 17,
+                move_number: 2,
+                san: "Nd5".to_string(),
+                source: ChessMoveError::IllegalMove,
+            },
+            error
+        );
+        assert_eq!(
+            "Move 2 (Nd5) at line 3, column 17 can't be played: The move is illegal",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn test_reader_resynchronises() {
+        let file = fs::File::open("tests/pgn_games/database.pgn").expect("Can't open PGN file");
+        let games: Vec<_> = PgnReader::new(file).collect();
+        assert_eq!(3, games.len());
+
+        let first = games[0].as_ref().unwrap();
+        assert_eq!(1, first.line);
+        assert_eq!(Some("Scholar's mate"), first.tag("Event"));
+        assert_eq!(3, first.tags.len());
+        assert_eq!(
+            "1. e4 e5 2. Bc4 Nc6 3. Qh5 Nf6 4. Qxf7# 1-0",
+            first.movetext
+        );
+        first.game().unwrap().play().unwrap();
+
+        // The error points at the line of the whole file
+        match &games[1] {
+            Err(PgnError::Syntax { line, col, .. }) => assert_eq!((11, 16), (*line, *col)),
+            other => panic!("Expected a syntax error, got {:?}", other),
+        }
+
+        let last = games[2].as_ref().unwrap();
+        assert_eq!(13, last.line);
+        assert_eq!(Some("*"), last.tag("Result"));
+        assert_eq!("1. Nf3 Nf6 2. g3\ng6 *", last.movetext);
+        last.game().unwrap().play().unwrap();
+    }
+
+    #[test]
+    fn test_reader_games_without_blank_lines() {
+        let input = "[Event \"A\"]\n1. e4 *\n[Event \"B\"]\n[Site \"?\"]\n1. d4 *";
+        let events: Vec<String> = PgnReader::new(input.as_bytes())
+            .map(|game| game.unwrap().tag("Event").unwrap().to_string())
+            .collect();
+        assert_eq!(vec!["A", "B"], events);
+        assert_eq!(0, PgnReader::new("\n\n".as_bytes()).count());
+    }
+
+    #[test]
+    fn test_reader_game_boundaries() {
+        // A comment line that looks like a tag doesn't start a new game
+        let input = "[Event \"A\"]\n[Result \"*\"]\n\n1. e4 {a comment\n[%clk 0:01:00]} e5 *\n\n\
+            [Event \"B\"]\n\n1. d4 d5 *\n";
+        let games: Vec<_> = PgnReader::new(input.as_bytes())
+            .map(|game| game.unwrap())
+            .collect();
+        assert_eq!(2, games.len());
+        assert_eq!("1. e4 {a comment\n[%clk 0:01:00]} e5 *", games[0].movetext);
+        assert_eq!((Some("B"), 7), (games[1].tag("Event"), games[1].line));
+
+        // Games without tags end with their result
+        let input = "1. e4 e5 *\n\n1. d4 d5 *\n";
+        let movetexts: Vec<String> = PgnReader::new(input.as_bytes())
+            .map(|game| game.unwrap().movetext)
+            .collect();
+        assert_eq!(vec!["1. e4 e5 *", "1. d4 d5 *"], movetexts);
+    }
+
+    #[test]
+    fn test_annotated_game() {
+        let input =
+            fs::read_to_string("tests/pgn_games/annotated.pgn").expect("Can't open PGN file");
+        let game = PGNGame::new(&input).unwrap();
+        let movetext = game.movetext();
+        assert_eq!(vec!["The Italian Game"], movetext.comments);
+        assert_eq!(15, movetext.moves.len());
+
+        // 3. Bc4 $1
+        assert_eq!("Bc4", movetext.moves[4].san);
+        assert_eq!(vec![1], movetext.moves[4].nags);
+
+        // The variation replaces 3... Bc5 and has a nested variation
+        let bc5 = &movetext.moves[5];
+        assert_eq!(1, bc5.variations.len());
+        let two_knights = &bc5.variations[0];
+        let sans: Vec<&str> = two_knights.moves.iter().map(|m| m.san.as_str()).collect();
+        assert_eq!(vec!["Nf6", "Ng5", "d5", "exd5", "Na5"], sans);
+        assert_eq!(vec!["Two Knights"], two_knights.moves[0].comments);
+        assert_eq!(vec![14], two_knights.moves[4].nags);
+        let traxler = &two_knights.moves[2].variations[0];
+        assert_eq!(vec![5], traxler.moves[0].nags);
+        assert_eq!("Bxf7+", traxler.moves[1].san);
+
+        // Line comments, suffixes and where the moves were read
+        assert_eq!(vec!["the main line"], movetext.moves[6].comments);
+        assert_eq!(vec![6], movetext.moves[8].nags);
+        assert_eq!(vec![3], movetext.moves[14].nags);
+        assert_eq!((10, 63), (movetext.moves[6].line, movetext.moves[6].col));
+
+        game.play().unwrap();
+
+        // Escaped quotes and empty tag values
+        let record = PgnReader::new(input.as_bytes()).next().unwrap().unwrap();
+        assert_eq!(Some("Coach \"The Hammer\""), record.tag("White"));
+        assert_eq!(Some(""), record.tag("Black"));
+    }
+
+    #[test]
+    fn test_annotation_errors() {
+        let misplaced = [
+            // A glyph before any move
+            "$1 1. e4 *",
+            // A variation before any move
+            "(1. d4) 1. e4 *",
+            // An unclosed variation
+            "1. e4 (1. d4 *",
+            // Glyphs are bytes
+            "1. e4 $256 *",
+        ];
+        for input in misplaced {
+            assert!(
+                matches!(PGNGame::new(input), Err(PgnError::Syntax { .. })),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_write_annotated_game() {
+        let input =
+            fs::read_to_string("tests/pgn_games/annotated.pgn").expect("Can't open PGN file");
+        let game = PGNGame::new(&input).unwrap();
+        let pgn = game.to_pgn();
+        let expected = r#"[Event "Annotated training game"]
+[Site "?"]
+[Date "2024.03.01"]
+[Round "-"]
+[White "Coach \"The Hammer\""]
+[Black ""]
+[Result "*"]
+
+{The Italian Game} 1. e4 e5 2. Nf3 Nc6 3. Bc4 $1 Bc5 (3... Nf6 {Two Knights}
+4. Ng5 d5 (4... Bc5 $5 {Traxler} 5. Bxf7+) 5. exd5 Na5 $14) 4. c3 {the main
+line} 4... Nf6 5. d4 $6 exd4 6. cxd4 Bb4+ 7. Nc3 Nxe4 8. O-O $3 *
+"#;
+        assert_eq!(expected, pgn);
+        assert!(pgn.lines().all(|line| line.len() <= 80));
+
+        // Loading the exported game gives back the same game
+        let reloaded = PGNGame::new(&pgn).unwrap();
+        assert_eq!(pgn, reloaded.to_pgn());
+        assert_eq!(game.movetext(), reloaded.movetext());
+        reloaded.play().unwrap();
+    }
+
+    #[test]
+    fn test_write_played_game() {
+        let board = INITIAL_FEN_BOARD.parse_fen().unwrap();
+        let moves: Vec<Move> = ["e2e4", "e7e5", "f1c4", "b8c6", "d1h5", "g8f6", "h5f7"]
+            .iter()
+            .map(|m| Move::from_str(m).unwrap())
+            .collect();
+        let mut line = PgnLine::from_moves(&board, &moves).unwrap();
+        line.moves[5].nags.push(4);
+        line.moves[5].comments.push("Nf6 loses at once".to_string());
+        let tags = [
+            ("Round".to_string(), "1".to_string()),
+            ("ECO".to_string(), "C20".to_string()),
+            ("Annotator".to_string(), "Me".to_string()),
+            ("Result".to_string(), "0-1".to_string()),
+        ];
+        let pgn = write_pgn(&tags, &line, "1-0");
+        let expected = r#"[Event "?"]
+[Site "?"]
+[Date "????.??.??"]
+[Round "1"]
+[White "?"]
+[Black "?"]
+[Result "1-0"]
+[Annotator "Me"]
+[ECO "C20"]
+
+1. e4 e5 2. Bc4 Nc6 3. Qh5 Nf6 $4 {Nf6 loses at once} 4. Qxf7# 1-0
+"#;
+        assert_eq!(expected, pgn);
+        PGNGame::new(&pgn).unwrap().play().unwrap();
+
+        // Only legal moves can be written
+        let illegal = [Move::from_str("e2e5").unwrap()];
+        assert_eq!(
+            Err(ChessMoveError::IllegalMove),
+            PgnLine::from_moves(&board, &illegal)
+        );
+    }
+
+    #[test]
+    fn test_setup_position() {
+        let input = fs::read_to_string("tests/pgn_games/setup.pgn").expect("Can't open PGN file");
+        let game = PGNGame::new(&input).unwrap();
+        assert_eq!(1, game.movetext().moves.len());
+        // The movetext is numbered from the FEN position when written back
+        assert_eq!(input, game.to_pgn());
+
+        let tree = game.tree().unwrap();
+        assert_eq!(
+            "3r2k1/5ppp/8/8/8/8/5PPP/6K1 b - - 0 23",
+            tree.start().as_fen()
+        );
+        game.play().unwrap();
+    }
+
+    #[test]
+    fn test_setup_move_numbers() {
+        let fen = "[SetUp \"1\"]\n[FEN \"3r2k1/5ppp/8/8/8/8/5PPP/6K1 b - - 0 23\"]\n\n";
+        // Black moves first, at move 23
+        for movetext in ["Rd1# 0-1", "23... Rd1# 0-1", "23... h6 24. g3 Rd1+ 0-1"] {
+            assert!(
+                PGNGame::new(&format!("{fen}{movetext}")).is_ok(),
+                "{movetext}"
+            );
+        }
+        for (movetext, col) in [
+            ("1... Rd1# 0-1", 1),
+            ("23. Rd1# 0-1", 1),
+            ("23... h6 24... g3 *", 10),
+            ("23... Rd1# (22... h6) 0-1", 13),
+        ] {
+            match PGNGame::new(&format!("{fen}{movetext}")) {
+                Err(PgnError::Syntax { line, col: c, .. }) => {
+                    assert_eq!((4, col), (line, c), "{movetext}")
+                }
+                _ => panic!("{movetext} should not be valid"),
+            }
+        }
+
+        // Moves from the standard starting position are numbered as usual
+        assert!(PGNGame::new("1... e5 *").is_err());
+        assert!(PGNGame::new("2. e4 *").is_err());
+
+        let broken = "[FEN \"3r2k1/5ppp/8/8/8 b - - 0 23\"]\n\n*";
+        assert!(matches!(
+            PGNGame::new(broken),
+            Err(PgnError::InvalidFen {
+                line: 1,
+                col: 1,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_chess960_game() {
+        let tags = "[Variant \"Chess960\"]\n[SetUp \"1\"]\n\
+            [FEN \"1r4kr/pppppppp/8/8/8/8/PPPPPPPP/RK4R1 w KQkq - 0 1\"]\n\n";
+        let game = PGNGame::new(&format!("{tags}1. O-O-O O-O *")).unwrap();
+        assert!(game.start_position().chess960);
+        assert_eq!(
+            "1r3rk1/pppppppp/8/8/8/8/PPPPPPPP/2KR2R1 w - - 2 2",
+            game.final_position().unwrap().as_fen()
+        );
+
+        // Without the Variant tag the king on b1 can't castle
+        let standard = tags.replacen("[Variant \"Chess960\"]\n", "", 1);
+        assert!(matches!(
+            PGNGame::new(&format!("{standard}*")),
+            Err(PgnError::InvalidFen {
+                line: 2,
+                source: ParseError::InvalidCastlingRights,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_game_accessors() {
+        let game: PGNGame = {
+            let input =
+                fs::read_to_string("tests/pgn_games/promotion.pgn").expect("Can't open PGN file");
+            input.parse().unwrap()
+        };
+        assert_eq!(Some("Promotion and en passant"), game.tag("Event"));
+        assert_eq!(7, game.metadata().len());
+        assert_eq!("1-0", game.game_result());
+        assert_eq!(INITIAL_FEN_BOARD, game.start_position().as_fen());
+
+        let moves = game.moves().unwrap();
+        assert_eq!(10, moves.len());
+        assert_eq!(Move::from_str("e2e4").unwrap(), moves[0]);
+        assert_eq!(
+            vec!["e4", "Nf6", "e5", "d5", "exd6", "c5", "dxe7", "Qa5", "exf8=Q+", "Kxf8"],
+            game.san_moves().unwrap()
+        );
+
+        let plies = game.plies().unwrap();
+        assert_eq!(
+            "rnbqkb1r/ppp1pppp/3P1n2/8/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 3",
+            plies[4].board.as_fen()
+        );
+        assert_eq!(
+            "rnb2k1r/pp3ppp/5n2/q1p5/8/8/PPPP1PPP/RNBQKBNR w KQ - 0 6",
+            game.final_position().unwrap().as_fen()
+        );
+
+        // The moves are only checked when they are needed
+        let game: PGNGame = "1. e4 e5 2. Nf3 Nd5 *".parse().unwrap();
+        assert!(matches!(
+            game.moves(),
+            Err(PgnError::InvalidMove { move_number: 2, .. })
+        ));
+        assert_eq!(INITIAL_FEN_BOARD, game.start_position().as_fen());
+    }
+
+    #[derive(Default)]
+    struct Recorder {
+        started: bool,
+        ended: bool,
+        events: Vec<(usize, String, Option<Piece>, bool)>,
+    }
+
+    impl ReplayObserver for Recorder {
+        fn start(&mut self, _game: &PGNGame) {
+            self.started = true;
+        }
+
+        fn ply(&mut self, event: &PlyEvent) {
+            self.events
+                .push((event.ply, event.san.clone(), event.captured, event.check));
+        }
+
+        fn end(&mut self, _game: &PGNGame) {
+            self.ended = true;
+        }
+    }
+
+    #[test]
+    fn test_replay_events() {
+        let input =
+            fs::read_to_string("tests/pgn_games/promotion.pgn").expect("Can't open PGN file");
+        let game = PGNGame::new(&input).unwrap();
+        let mut recorder = Recorder::default();
+        game.replay(&mut recorder).unwrap();
+        assert!(recorder.started && recorder.ended);
+        assert_eq!(10, recorder.events.len());
+        let pawn = Piece::new(Color::Black, PieceType::Pawn);
+        // 3. exd6 captures en passant, 5. exf8=Q+ captures a bishop and gives check
+        assert_eq!(
+            (5, "exd6".to_string(), Some(pawn), false),
+            recorder.events[4]
+        );
+        assert_eq!(
+            (
+                9,
+                "exf8=Q+".to_string(),
+                Some(Piece::new(Color::Black, PieceType::Bishop)),
+                true
+            ),
+            recorder.events[8]
+        );
+
+        let mut fens = Vec::new();
+        game.replay(&mut |event: &PlyEvent| fens.push(event.fen.clone()))
+            .unwrap();
+        assert_eq!(
+            game.final_position().unwrap().as_fen(),
+            *fens.last().unwrap()
+        );
+
+        // Replay stops at the first move that can't be played
+        let game: PGNGame = "1. e4 e5 2. Nf3 Nd5 *".parse().unwrap();
+        let mut recorder = Recorder::default();
+        assert!(game.replay(&mut recorder).is_err());
+        assert_eq!(3, recorder.events.len());
+        assert!(!recorder.ended);
+    }
+
+    #[test]
+    fn test_pretty_printer() {
+        let input =
+            fs::read_to_string("tests/pgn_games/promotion.pgn").expect("Can't open PGN file");
+        let game = PGNGame::new(&input).unwrap();
+        let mut printer = PrettyPrinter::new(Vec::new());
+        game.replay(&mut printer).unwrap();
+        let output = String::from_utf8(printer.into_inner()).unwrap();
+        assert!(output.contains("| Event                | Promotion and en passant"));
+        assert!(output.contains("Move 3: White: exd6\n"));
+        assert!(output.contains("Move 5: Black: Kxf8\n"));
+        assert!(output.contains("| Game Result: 1-0\n"));
+    }
 }
@@ -1,5 +1,3 @@
-mod tests;
-
 #[cfg(test)]
 mod test {
     use ajedrez::{Color, Piece, PieceType};
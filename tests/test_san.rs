@@ -0,0 +1,164 @@
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use ajedrez::{
+        pos_from_str, to_san, ChessBoard, FENStringParsing, Move, SanError, INITIAL_FEN_BOARD,
+    };
+
+    fn san(board: &ChessBoard, mov: &str) -> String {
+        to_san(board, Move::from_str(mov).unwrap())
+    }
+
+    #[test]
+    fn test_san_simple_moves() {
+        let board = INITIAL_FEN_BOARD.parse_fen().unwrap();
+        assert_eq!("e4", san(&board, "e2e4"));
+        assert_eq!("Nc3", san(&board, "b1c3"));
+
+        let board = "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2"
+            .parse_fen()
+            .unwrap();
+        assert_eq!("exd5", san(&board, "e4d5"));
+        assert_eq!("Bb5+", san(&board, "f1b5"));
+    }
+
+    #[test]
+    fn test_san_en_passant_and_promotion() {
+        let board = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1".parse_fen().unwrap();
+        assert_eq!("exd6", san(&board, "e5d6"));
+
+        let board = "3r3k/4P3/8/8/8/8/8/4K3 w - - 0 1".parse_fen().unwrap();
+        assert_eq!("e8=Q+", san(&board, "e7e8q"));
+        assert_eq!("exd8=N", san(&board, "e7d8n"));
+    }
+
+    #[test]
+    fn test_san_castling() {
+        let board = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1".parse_fen().unwrap();
+        let castling: Vec<String> = board
+            .legal_moves()
+            .into_iter()
+            .filter(|m| m.castling)
+            .map(|m| to_san(&board, m))
+            .collect();
+        assert_eq!(vec!["O-O", "O-O-O"], castling);
+        // Not flagged as castling, but it is
+        assert_eq!("O-O", san(&board, "e1g1"));
+    }
+
+    #[test]
+    fn test_san_disambiguation() {
+        // Knights on b1 and f3 can both reach d2: the file is enough
+        let board = "4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1".parse_fen().unwrap();
+        assert_eq!("Nbd2", san(&board, "b1d2"));
+        // Rooks on a1 and a5 share the file: the rank is needed
+        let board = "4k3/8/8/R7/8/8/8/R3K3 w - - 0 1".parse_fen().unwrap();
+        assert_eq!("R1a3", san(&board, "a1a3"));
+        // Queens on h4, e4 and h1 can all reach e1: file and rank
+        let board = "1k6/8/8/8/4Q2Q/8/8/2K4Q w - - 0 1".parse_fen().unwrap();
+        assert_eq!("Qh4e1", san(&board, "h4e1"));
+        // A pinned knight doesn't need to be told apart
+        let board = "4r2k/8/8/8/1N6/4N3/8/4K3 w - - 0 1".parse_fen().unwrap();
+        assert_eq!("Nd5", san(&board, "b4d5"));
+    }
+
+    #[test]
+    fn test_san_checkmate() {
+        // Fool's mate
+        let board = "rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 2"
+            .parse_fen()
+            .unwrap();
+        assert_eq!("Qh4#", san(&board, "d8h4"));
+    }
+
+    #[test]
+    fn test_parse_san() {
+        let board = INITIAL_FEN_BOARD.parse_fen().unwrap();
+        assert_eq!(
+            Move::from_str("e2e4").unwrap(),
+            board.parse_san("e4").unwrap()
+        );
+        assert_eq!(
+            Move::from_str("g1f3").unwrap(),
+            board.parse_san("Nf3+!?").unwrap()
+        );
+
+        let board = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1".parse_fen().unwrap();
+        for (san, col) in [("O-O", 6), ("0-0", 6), ("O-O-O", 2), ("0-0-0#", 2)] {
+            let mov = board.parse_san(san).unwrap();
+            assert!(mov.castling);
+            assert_eq!(col, mov.to.1);
+        }
+
+        let board = "3r3k/4P3/8/8/8/8/8/4K3 w - - 0 1".parse_fen().unwrap();
+        assert_eq!(
+            Move::from_str("e7d8n").unwrap(),
+            board.parse_san("exd8=N").unwrap()
+        );
+        assert_eq!(
+            Move::from_str("e7e8q").unwrap(),
+            board.parse_san("e8Q+").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_san_disambiguation() {
+        let board = "1k6/8/8/8/4Q2Q/8/8/2K4Q w - - 0 1".parse_fen().unwrap();
+        assert_eq!(
+            pos_from_str("h4").unwrap(),
+            board.parse_san("Qh4e1").unwrap().from
+        );
+        assert_eq!(
+            pos_from_str("e4").unwrap(),
+            board.parse_san("Qee1").unwrap().from
+        );
+        assert_eq!(
+            Err(SanError::AmbiguousMove("Qhe1".to_string())),
+            board.parse_san("Qhe1")
+        );
+        assert_eq!(
+            Err(SanError::AmbiguousMove("Qe1".to_string())),
+            board.parse_san("Qe1")
+        );
+
+        // The knight on e3 is pinned, so Nd5 can only be the one on b4
+        let board = "4r2k/8/8/8/1N6/4N3/8/4K3 w - - 0 1".parse_fen().unwrap();
+        assert_eq!(
+            pos_from_str("b4").unwrap(),
+            board.parse_san("Nd5").unwrap().from
+        );
+    }
+
+    #[test]
+    fn test_parse_san_errors() {
+        let board = INITIAL_FEN_BOARD.parse_fen().unwrap();
+        assert_eq!(Err(SanError::EmptyString), board.parse_san("  "));
+        assert_eq!(Err(SanError::EmptyString), board.parse_san("+"));
+        for san in ["Zf3", "e9", "Nf", "Ke4=Q", "Nbcd3"] {
+            assert_eq!(
+                Err(SanError::InvalidSyntax(san.to_string())),
+                board.parse_san(san),
+                "{san}"
+            );
+        }
+        for san in ["e5", "e4Q", "Nf4", "O-O", "Bb5"] {
+            assert_eq!(
+                Err(SanError::IllegalMove(san.to_string())),
+                board.parse_san(san),
+                "{san}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_san_round_trip() {
+        let board = "r3k2r/pPppqpb1/bn2pnp1/2pPN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq c6 0 1"
+            .parse_fen()
+            .unwrap();
+        for mov in board.legal_moves() {
+            let san = to_san(&board, mov);
+            assert_eq!(Ok(mov), board.parse_san(&san), "{san}");
+        }
+    }
+}
@@ -0,0 +1,89 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicBool;
+    use std::time::Duration;
+
+    use ajedrez::{evaluate, search, FENStringParsing, Score, SearchInfo, SearchLimits};
+
+    fn run(fen: &str, limits: SearchLimits) -> (Option<String>, Vec<SearchInfo>) {
+        let board = fen.parse_fen().unwrap();
+        let mut infos = Vec::new();
+        let best = search(&board, &limits, &AtomicBool::new(false), |info| {
+            infos.push(info.clone())
+        });
+        (best.map(|mov| mov.to_string()), infos)
+    }
+
+    fn depth(depth: u32) -> SearchLimits {
+        SearchLimits {
+            depth: Some(depth),
+            time: None,
+        }
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let board = ajedrez::INITIAL_FEN_BOARD.parse_fen().unwrap();
+        assert_eq!(0, evaluate(&board));
+        // Black is a queen down, the score is seen from the side to move
+        let board = "rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1"
+            .parse_fen()
+            .unwrap();
+        assert!(evaluate(&board) < -800);
+    }
+
+    #[test]
+    fn test_search_mate() {
+        let (best, infos) = run("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", depth(3));
+        assert_eq!(Some("a1a8".to_string()), best);
+        assert_eq!(Score::Mate(1), infos.last().unwrap().score);
+
+        // Black's only move lets the rook mate
+        let (best, infos) = run("7k/R7/6K1/8/8/8/8/8 b - - 0 1", depth(3));
+        assert_eq!(Some("h8g8".to_string()), best);
+        assert_eq!(Score::Mate(-1), infos.last().unwrap().score);
+    }
+
+    #[test]
+    fn test_search_captures() {
+        // The queen on d5 is free
+        let (best, infos) = run("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1", depth(2));
+        assert_eq!(Some("d1d5".to_string()), best);
+        let info = infos.last().unwrap();
+        assert_eq!(2, info.depth);
+        assert_eq!("d1d5", info.pv[0].to_string());
+        assert!(matches!(info.score, Score::Centipawns(cp) if cp > 0));
+    }
+
+    #[test]
+    fn test_search_limits() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let (best, infos) = run(fen, depth(3));
+        assert!(best.is_some());
+        assert_eq!(
+            vec![1, 2, 3],
+            infos.iter().map(|i| i.depth).collect::<Vec<_>>()
+        );
+
+        let limits = SearchLimits {
+            depth: None,
+            time: Some(Duration::from_millis(100)),
+        };
+        let board = fen.parse_fen().unwrap();
+        let best = search(&board, &limits, &AtomicBool::new(false), |_| {}).unwrap();
+        assert!(board.legal_moves().contains(&best));
+
+        // A search stopped before it starts still has a move to play
+        let best = search(
+            &board,
+            &SearchLimits::default(),
+            &AtomicBool::new(true),
+            |_| {},
+        );
+        assert!(board.legal_moves().contains(&best.unwrap()));
+
+        // Checkmate and stalemate
+        assert_eq!(None, run("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1", depth(2)).0);
+        assert_eq!(None, run("7k/5Q2/8/8/8/8/8/6K1 b - - 0 1", depth(2)).0);
+    }
+}
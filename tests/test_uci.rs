@@ -0,0 +1,65 @@
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use ajedrez::{ChessBoard, ChessMoveError, FENStringParsing, Move, ParseError, PieceType};
+
+    #[test]
+    fn test_parse_uci() {
+        let board = "r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1"
+            .parse_fen()
+            .unwrap();
+        // Plain moves, en passant included, are read as they are written
+        for uci in ["e1f1", "a1a8", "e5d6", "e5e6"] {
+            assert_eq!(Move::from_str(uci).unwrap(), board.parse_uci(uci).unwrap());
+        }
+
+        let kingside = board.parse_uci("e1g1").unwrap();
+        assert!(kingside.castling);
+        assert!(board.parse_uci("e1c1").unwrap().castling);
+        assert!(board.legal_moves().contains(&kingside));
+
+        let promotion = board.parse_uci("b7a8q").unwrap();
+        assert_eq!(Some(PieceType::Queen), promotion.promotion);
+        assert!(board.legal_moves().contains(&promotion));
+        assert_eq!(
+            Err(ParseError::MissingPromotionPiece),
+            board.parse_uci("b7b8")
+        );
+        assert_eq!(
+            Err(ParseError::InvalidPromotionPiece),
+            board.parse_uci("e5e6q")
+        );
+        assert_eq!(Err(ParseError::StringTooShort), board.parse_uci("e1"));
+
+        let null = board.parse_uci("0000").unwrap();
+        assert!(null.is_null());
+        assert_eq!("0000", board.to_uci(null));
+        let mut board = board;
+        assert_eq!(Err(ChessMoveError::IllegalMove), board.make_move(null));
+    }
+
+    #[test]
+    fn test_uci_round_trip() {
+        let mut boards: Vec<ChessBoard> = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ]
+        .iter()
+        .map(|fen| fen.parse_fen().unwrap())
+        .collect();
+        let mut chess960 = "1r4kr/pppppppp/8/8/8/8/PPPPPPPP/RK4R1 w KQkq - 0 1"
+            .parse_fen()
+            .unwrap();
+        chess960.chess960 = true;
+        boards.push(chess960);
+
+        for board in boards {
+            for mov in board.legal_moves() {
+                let uci = board.to_uci(mov);
+                assert_eq!(mov, board.parse_uci(&uci).unwrap(), "{uci}");
+            }
+        }
+    }
+}
@@ -0,0 +1,66 @@
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    /// Runs the `uci` binary with the given commands and returns what it wrote
+    fn run_engine(commands: &[&str]) -> Vec<String> {
+        let mut engine = Command::new(env!("CARGO_BIN_EXE_uci"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut stdin = engine.stdin.take().unwrap();
+        for command in commands {
+            writeln!(stdin, "{command}").unwrap();
+        }
+        drop(stdin);
+        let output = engine.wait_with_output().unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout)
+            .unwrap()
+            .lines()
+            .filter(|line| !line.starts_with("info depth"))
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_chess960_position() {
+        let output = run_engine(&[
+            "setoption name UCI_Chess960 value true",
+            "position fen 1r4kr/pppppppp/8/8/8/8/PPPPPPPP/RK4R1 w KQkq - 0 1 moves b1g1",
+            "go depth 1",
+            "quit",
+        ]);
+        assert_eq!(1, output.len(), "{output:?}");
+        assert!(output[0].starts_with("bestmove "));
+        assert_ne!("bestmove 0000", output[0]);
+    }
+
+    #[test]
+    fn test_invalid_position() {
+        // The engine doesn't search the previous position after one it can't read
+        let output = run_engine(&["position fen 8/8/8 w - - 0 1", "go depth 2", "quit"]);
+        assert!(
+            output[0].starts_with("info string Invalid FEN"),
+            "{output:?}"
+        );
+        assert_eq!("bestmove 0000", output.last().unwrap());
+
+        let output = run_engine(&["position startpos moves e2e4 e2e4", "go depth 2", "quit"]);
+        assert_eq!("info string Illegal move e2e4", output[0]);
+        assert_eq!("bestmove 0000", output.last().unwrap());
+
+        // A valid position sets things right
+        let output = run_engine(&[
+            "position fen 8/8/8 w - - 0 1",
+            "position startpos moves e2e4",
+            "go depth 2",
+            "quit",
+        ]);
+        assert_eq!(2, output.len(), "{output:?}");
+        assert!(output[1].starts_with("bestmove "));
+        assert_ne!("bestmove 0000", output[1]);
+    }
+}
@@ -0,0 +1,111 @@
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use ajedrez::{BoardAsFEN, ChessBoard, ChessMove, FENStringParsing, Move, INITIAL_FEN_BOARD};
+
+    fn play(board: &mut ChessBoard, moves: &[&str]) {
+        for mov in moves {
+            if *mov == "O-O" {
+                board
+                    .castle(board.active_color, ChessMove::CastleKingside)
+                    .unwrap();
+            } else {
+                board.move_piece(Move::from_str(mov).unwrap()).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_polyglot_keys() {
+        // The reference keys of the Polyglot book format specification
+        let mut board = INITIAL_FEN_BOARD.parse_fen().unwrap();
+        assert_eq!(0x463b96181691fc9c, board.hash());
+        let keys = [
+            ("e2e4", 0x823c9b50fd114196),
+            ("d7d5", 0x0756b94461c50fb0),
+            ("e4e5", 0x662fafb965db29d4),
+            // En passant is possible, so the file of f6 is part of the key
+            ("f7f5", 0x22a48b5a8e47ff78),
+            ("e1e2", 0x652a607ca3f242c1),
+            ("e8f7", 0x00fdd303c946bdd9),
+        ];
+        for (mov, key) in keys {
+            play(&mut board, &[mov]);
+            assert_eq!(key, board.hash(), "after {mov}");
+        }
+
+        let mut board = INITIAL_FEN_BOARD.parse_fen().unwrap();
+        play(&mut board, &["a2a4", "b7b5", "h2h4", "b5b4", "c2c4"]);
+        assert_eq!(0x3c8123ea7b067637, board.hash());
+        play(&mut board, &["b4c3", "a1a3"]);
+        assert_eq!(0x5c3f9b829b279560, board.hash());
+
+        // The keys of positions read from FEN match the ones of played moves
+        let fen = "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3";
+        assert_eq!(0x22a48b5a8e47ff78, fen.parse_fen().unwrap().hash());
+        let fen = "rnbqkbnr/p1pppppp/8/8/PpP4P/8/1P1PPPP1/RNBQKBNR b KQkq c3 0 3";
+        assert_eq!(0x3c8123ea7b067637, fen.parse_fen().unwrap().hash());
+    }
+
+    #[test]
+    fn test_incremental_hash() {
+        let mut board = INITIAL_FEN_BOARD.parse_fen().unwrap();
+        // Captures, castling, en passant and promotion
+        let moves = [
+            "e2e4", "d7d5", "e4d5", "c7c5", "d5c6", "g8f6", "c6b7", "e7e6", "g1f3", "f8e7", "f1e2",
+            "O-O", "O-O", "a7a6", "b7a8q",
+        ];
+        for mov in moves {
+            play(&mut board, &[mov]);
+            let from_scratch = board.as_fen().parse_fen().unwrap();
+            assert_eq!(from_scratch.hash(), board.hash(), "after {mov}");
+        }
+    }
+
+    #[test]
+    fn test_unmake_restores_hash() {
+        let mut board = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"
+            .parse_fen()
+            .unwrap();
+        let hash = board.hash();
+        for mov in board.legal_moves() {
+            let undo = board.make_move(mov).unwrap();
+            assert_ne!(hash, board.hash());
+            board.unmake_move(undo);
+            assert_eq!(hash, board.hash());
+        }
+    }
+
+    #[test]
+    fn test_hash_position_features() {
+        let mut a = INITIAL_FEN_BOARD.parse_fen().unwrap();
+        let mut b = INITIAL_FEN_BOARD.parse_fen().unwrap();
+        // Transpositions give the same key
+        play(&mut a, &["g1f3", "g8f6", "b1c3", "b8c6"]);
+        play(&mut b, &["b1c3", "b8c6", "g1f3", "g8f6"]);
+        assert_eq!(a.hash(), b.hash());
+
+        let key = |fen: &str| fen.parse_fen().unwrap().hash();
+        let fen = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1";
+        // No black pawn can capture en passant, so the target square doesn't count ...
+        assert_eq!(
+            key(fen),
+            key("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1")
+        );
+        // ... but the side to move and the castling rights do
+        assert_ne!(
+            key(fen),
+            key("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 1")
+        );
+        assert_ne!(
+            key(fen),
+            key("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b Kkq - 0 1")
+        );
+        // With a black pawn on d4 the en passant target is part of the key
+        assert_ne!(
+            key("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"),
+            key("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1")
+        );
+    }
+}